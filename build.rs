@@ -0,0 +1,12 @@
+fn main() {
+    // Use the protoc shipped with protoc-bin-vendored, building does not need a system install
+    std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path().unwrap());
+
+    tonic_build::configure()
+        .build_client(false)
+        .compile(
+            &["proto/photon.proto", "proto/vector_tile.proto"],
+            &["proto"],
+        )
+        .unwrap();
+}
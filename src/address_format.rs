@@ -0,0 +1,182 @@
+use crate::response::PhotonResponseProperties;
+
+const DEFAULT_TEMPLATE: &str =
+    "{name}\n{housenumber} {street}\n{postcode} {city}\n{state}\n{country}";
+const STREET_FIRST_TEMPLATE: &str = "{name}\n{street} {housenumber}\n{postcode} {city}\n{country}";
+const HOUSENUMBER_FIRST_TEMPLATE: &str =
+    "{name}\n{housenumber} {street}\n{postcode} {city}\n{country}";
+const GB_TEMPLATE: &str =
+    "{name}\n{housenumber} {street}\n{locality}\n{city}\n{postcode}\n{country}";
+const NORTH_AMERICA_TEMPLATE: &str =
+    "{name}\n{housenumber} {street}\n{city}, {state} {postcode}\n{country}";
+
+fn template_for(countrycode: &Option<String>) -> &'static str {
+    let countrycode = match countrycode {
+        Some(countrycode) => countrycode.to_uppercase(),
+        None => return DEFAULT_TEMPLATE,
+    };
+
+    return match countrycode.as_str() {
+        "AT" | "BE" | "CH" | "CZ" | "DE" | "DK" | "ES" | "FI" | "HR" | "IS" | "IT" | "LI"
+        | "NL" | "NO" | "PL" | "PT" | "SE" | "SI" | "SK" => STREET_FIRST_TEMPLATE,
+        "FR" | "LU" | "MC" => HOUSENUMBER_FIRST_TEMPLATE,
+        "GB" | "IE" => GB_TEMPLATE,
+        "US" | "CA" | "AU" => NORTH_AMERICA_TEMPLATE,
+        _ => DEFAULT_TEMPLATE,
+    };
+}
+
+fn component<'a>(properties: &'a PhotonResponseProperties, name: &str) -> Option<&'a String> {
+    return match name {
+        "name" => properties.name.as_ref(),
+        "housenumber" => properties.housenumber.as_ref(),
+        "street" => properties.street.as_ref(),
+        "locality" => properties.locality.as_ref(),
+        "district" => properties.district.as_ref(),
+        "postcode" => properties.postcode.as_ref(),
+        "city" => properties.city.as_ref(),
+        "county" => properties.county.as_ref(),
+        "state" => properties.state.as_ref(),
+        "country" => properties.country.as_ref(),
+        _ => None,
+    };
+}
+
+fn render_line(line: &str, properties: &PhotonResponseProperties) -> String {
+    let mut rendered = String::new();
+    let mut rest = line;
+
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        if let Some(value) = component(properties, &rest[start + 1..end]) {
+            rendered.push_str(value);
+        }
+        rest = &rest[end + 1..];
+    }
+    rendered.push_str(rest);
+
+    let rendered = rendered.split_whitespace().collect::<Vec<&str>>().join(" ");
+    return rendered
+        .replace(" ,", ",")
+        .trim_matches(|c: char| c == ',' || c.is_whitespace())
+        .to_string();
+}
+
+pub fn format_address(properties: &PhotonResponseProperties) -> Option<String> {
+    let mut lines: Vec<String> = vec![];
+
+    for line in template_for(&properties.countrycode).lines() {
+        let rendered = render_line(line, properties);
+        if !rendered.is_empty() && !lines.contains(&rendered) {
+            lines.push(rendered);
+        }
+    }
+
+    if lines.is_empty() {
+        return None;
+    }
+
+    return Some(lines.join("\n"));
+}
+
+pub fn format_label(properties: &PhotonResponseProperties) -> Option<String> {
+    return properties
+        .formatted
+        .as_ref()
+        .map(|formatted| formatted.replace('\n', ", "));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn properties(countrycode: &str) -> PhotonResponseProperties {
+        return PhotonResponseProperties {
+            countrycode: Some(countrycode.to_string()),
+            name: Some("Rathaus".to_string()),
+            housenumber: Some("12".to_string()),
+            street: Some("Hauptstraße".to_string()),
+            postcode: Some("10115".to_string()),
+            city: Some("Berlin".to_string()),
+            country: Some("Germany".to_string()),
+            ..Default::default()
+        };
+    }
+
+    #[test]
+    fn street_before_housenumber() {
+        assert_eq!(
+            format_address(&properties("de")).unwrap(),
+            "Rathaus\nHauptstraße 12\n10115 Berlin\nGermany"
+        );
+    }
+
+    #[test]
+    fn housenumber_before_street() {
+        assert_eq!(
+            format_address(&properties("FR")).unwrap(),
+            "Rathaus\n12 Hauptstraße\n10115 Berlin\nGermany"
+        );
+    }
+
+    #[test]
+    fn north_america_joins_city_and_state() {
+        let properties = PhotonResponseProperties {
+            state: Some("NY".to_string()),
+            ..properties("US")
+        };
+
+        assert_eq!(
+            format_address(&properties).unwrap(),
+            "Rathaus\n12 Hauptstraße\nBerlin, NY 10115\nGermany"
+        );
+    }
+
+    #[test]
+    fn drops_separators_of_missing_components() {
+        let properties = PhotonResponseProperties {
+            countrycode: Some("US".to_string()),
+            city: Some("Springfield".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(format_address(&properties).unwrap(), "Springfield");
+    }
+
+    #[test]
+    fn skips_repeated_lines() {
+        let properties = PhotonResponseProperties {
+            countrycode: Some("XX".to_string()),
+            name: Some("Monaco".to_string()),
+            city: None,
+            state: Some("Monaco".to_string()),
+            country: Some("Monaco".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(format_address(&properties).unwrap(), "Monaco");
+    }
+
+    #[test]
+    fn nothing_to_format() {
+        assert!(format_address(&PhotonResponseProperties::default()).is_none());
+    }
+
+    #[test]
+    fn label_is_single_line() {
+        let properties = PhotonResponseProperties {
+            formatted: Some("Rathaus\nHauptstraße 12".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            format_label(&properties).unwrap(),
+            "Rathaus, Hauptstraße 12"
+        );
+        assert!(format_label(&PhotonResponseProperties::default()).is_none());
+    }
+}
@@ -53,11 +53,7 @@ pub fn address_types() -> Vec<AddressType> {
 
 #[allow(dead_code)]
 pub fn from_rank(rank: i64) -> Option<AddressType> {
-    let address_types = address_types();
-    for a in address_types {
-        if rank >= a.min_rank && rank <= a.max_rank {
-            return Some(a);
-        }
-    }
-    return None;
+    return address_types()
+        .into_iter()
+        .find(|a| rank >= a.min_rank && rank <= a.max_rank);
 }
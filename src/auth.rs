@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::config::ApiKeyConfig;
+use crate::errors::AuthError;
+use crate::metrics::Metrics;
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+pub const API_KEY_PARAM: &str = "api_key";
+
+const API_KEY_REQUESTS_METRIC: &str = "photon_api_key_requests_total";
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+pub struct AuthRequest<'a> {
+    pub key: Option<&'a str>,
+    pub endpoint: &'a str,
+    pub cost: u32,
+    pub limits: Vec<i64>,
+    pub languages: Vec<&'a str>,
+}
+
+// The key's max_limit, added to the request by the API key middleware so handlers can cap the
+// sizes they choose themselves
+#[derive(Debug, Clone, Copy)]
+pub struct MaxLimit(pub Option<i64>);
+
+struct ApiKey {
+    name: String,
+    config: ApiKeyConfig,
+}
+
+struct RateWindow {
+    started: Instant,
+    count: u32,
+}
+
+pub struct Authenticator {
+    keys: Vec<ApiKey>,
+    windows: Mutex<HashMap<String, RateWindow>>,
+    metrics: Arc<Metrics>,
+}
+
+impl Authenticator {
+    pub fn new(keys: HashMap<String, ApiKeyConfig>, metrics: Arc<Metrics>) -> Self {
+        let keys = keys
+            .into_iter()
+            .map(|(name, config)| ApiKey { name, config })
+            .collect();
+
+        return Authenticator {
+            keys,
+            windows: Mutex::new(HashMap::new()),
+            metrics,
+        };
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        return !self.keys.is_empty();
+    }
+
+    pub fn is_valid(&self, key: &str) -> bool {
+        return self.find(key).is_some();
+    }
+
+    pub fn max_limit(&self, key: Option<&str>) -> MaxLimit {
+        return MaxLimit(
+            key.and_then(|key| self.find(key))
+                .and_then(|api_key| api_key.config.max_limit),
+        );
+    }
+
+    pub fn authorize(&self, request: &AuthRequest) -> Result<(), AuthError> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+
+        let api_key = match request.key {
+            Some(key) => match self.find(key) {
+                Some(api_key) => api_key,
+                None => {
+                    self.record("invalid", request, "invalid_key");
+                    return Err(AuthError::InvalidKey);
+                }
+            },
+            None => {
+                self.record("anonymous", request, "missing_key");
+                return Err(AuthError::MissingKey);
+            }
+        };
+
+        let result = self.check(api_key, request);
+        let outcome = match &result {
+            Ok(()) => "allowed",
+            Err(AuthError::Endpoint(_)) => "endpoint_denied",
+            Err(AuthError::Limit { .. }) => "limit_denied",
+            Err(AuthError::Lang { .. }) => "lang_denied",
+            Err(AuthError::RateLimited { .. }) => "rate_limited",
+            Err(_) => "denied",
+        };
+        self.record(&api_key.name, request, outcome);
+
+        return result;
+    }
+
+    // Compares against every configured key so the response time does not depend on which key matched
+    fn find(&self, key: &str) -> Option<&ApiKey> {
+        let mut found = None;
+        for api_key in &self.keys {
+            if constant_time_eq(api_key.config.key.as_bytes(), key.as_bytes()) {
+                found = Some(api_key);
+            }
+        }
+        return found;
+    }
+
+    fn check(&self, api_key: &ApiKey, request: &AuthRequest) -> Result<(), AuthError> {
+        let config = &api_key.config;
+
+        if let Some(endpoints) = &config.endpoints {
+            if !endpoints
+                .iter()
+                .any(|endpoint| endpoint_matches(endpoint, request.endpoint))
+            {
+                return Err(AuthError::Endpoint(request.endpoint.to_string()));
+            }
+        }
+
+        if let Some(max) = config.max_limit {
+            if let Some(value) = request.limits.iter().find(|limit| **limit > max) {
+                return Err(AuthError::Limit { value: *value, max });
+            }
+        }
+
+        if let Some(valid) = &config.languages {
+            if let Some(value) = request
+                .languages
+                .iter()
+                .find(|language| !valid.iter().any(|valid| valid == *language))
+            {
+                return Err(AuthError::Lang {
+                    value: value.to_string(),
+                    valid: valid.clone(),
+                });
+            }
+        }
+
+        if let Some(rate_limit) = config.rate_limit {
+            self.check_rate_limit(&api_key.name, rate_limit, request.cost)?;
+        }
+
+        return Ok(());
+    }
+
+    fn check_rate_limit(&self, name: &str, rate_limit: u32, cost: u32) -> Result<(), AuthError> {
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+        let window = windows.entry(name.to_string()).or_insert(RateWindow {
+            started: now,
+            count: 0,
+        });
+
+        if now.duration_since(window.started) >= RATE_LIMIT_WINDOW {
+            window.started = now;
+            window.count = 0;
+        }
+
+        if window.count.saturating_add(cost) > rate_limit {
+            let elapsed = now.duration_since(window.started);
+            let retry_after = RATE_LIMIT_WINDOW.saturating_sub(elapsed).as_secs().max(1);
+            return Err(AuthError::RateLimited { retry_after });
+        }
+
+        window.count += cost;
+        return Ok(());
+    }
+
+    fn record(&self, name: &str, request: &AuthRequest, outcome: &str) {
+        self.metrics.add(
+            API_KEY_REQUESTS_METRIC,
+            &[
+                ("api_key", name),
+                ("endpoint", request.endpoint),
+                ("outcome", outcome),
+            ],
+            request.cost as u64,
+        );
+    }
+}
+
+// "/tiles/*" allows every path below "/tiles/"
+fn endpoint_matches(allowed: &str, endpoint: &str) -> bool {
+    return match allowed.strip_suffix('*') {
+        Some(prefix) => endpoint.starts_with(prefix),
+        None => allowed == endpoint,
+    };
+}
+
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let difference = a
+        .iter()
+        .zip(b.iter())
+        .fold(0u8, |difference, (a, b)| difference | (a ^ b));
+    return difference == 0;
+}
@@ -0,0 +1,230 @@
+use elasticsearch::Elasticsearch;
+use photon_api::config::{load_api_config, load_language_config, load_ranking_config};
+use photon_api::elastic::{create_elasticsearch_client, send_photon_query};
+use photon_api::errors::PhotonError;
+use photon_api::evaluation::{
+    evaluate_ranking, load_evaluation_queries, mean_metrics, EvaluationQuery, OsmRef,
+    RankingMetrics,
+};
+use photon_api::metrics::Metrics;
+use photon_api::query::{build_search_query, QueryFilters, RankingProfile};
+use photon_api::validation::validate_location_bias;
+
+const USAGE: &str =
+    "usage: evaluate <queries.jsonl> [--k <k>] [--profile <name>] [--compare <name>]";
+
+struct EvaluationArgs {
+    queries_path: String,
+    k: usize,
+    profile: String,
+    compare: Option<String>,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = parse_args(std::env::args().skip(1).collect());
+
+    let config = load_api_config();
+    let languages = load_language_config();
+    let profiles = load_ranking_config();
+
+    let client =
+        create_elasticsearch_client(&config.elastic_cloud_id, &config.elastic_api_key).unwrap();
+
+    let contents = match std::fs::read_to_string(&args.queries_path) {
+        Ok(contents) => contents,
+        Err(err) => panic!("Could not read \"{}\": {}", args.queries_path, err),
+    };
+    let queries = match load_evaluation_queries(&contents) {
+        Ok(queries) => queries,
+        Err(err) => panic!("Invalid queries file \"{}\": {}", args.queries_path, err),
+    };
+
+    let get_profile = |name: &String| match profiles.get(name) {
+        Some(profile) => profile,
+        None => panic!("Unknown ranking profile \"{}\"", name),
+    };
+
+    let baseline = get_profile(&args.profile);
+    let baseline_metrics = run_queries(
+        &client,
+        &config.index,
+        &queries,
+        &languages,
+        baseline,
+        args.k,
+    )
+    .await
+    .unwrap();
+
+    let compare_metrics = match &args.compare {
+        Some(name) => Some(
+            run_queries(
+                &client,
+                &config.index,
+                &queries,
+                &languages,
+                get_profile(name),
+                args.k,
+            )
+            .await
+            .unwrap(),
+        ),
+        None => None,
+    };
+
+    for (i, query) in queries.iter().enumerate() {
+        let a = &baseline_metrics[i];
+        match &compare_metrics {
+            Some(compare_metrics) => {
+                let b = &compare_metrics[i];
+                println!(
+                    "{:<40} P@{k} {:.3} -> {:.3}  RR {:.3} -> {:.3}  nDCG@{k} {:.3} -> {:.3}",
+                    query.q,
+                    a.precision_at_k,
+                    b.precision_at_k,
+                    a.reciprocal_rank,
+                    b.reciprocal_rank,
+                    a.ndcg_at_k,
+                    b.ndcg_at_k,
+                    k = args.k,
+                );
+            }
+            None => println!(
+                "{:<40} P@{k} {:.3}  RR {:.3}  nDCG@{k} {:.3}",
+                query.q,
+                a.precision_at_k,
+                a.reciprocal_rank,
+                a.ndcg_at_k,
+                k = args.k,
+            ),
+        }
+    }
+
+    println!();
+    print_summary(&args.profile, &mean_metrics(&baseline_metrics), args.k);
+
+    if let (Some(name), Some(compare_metrics)) = (&args.compare, &compare_metrics) {
+        let a = mean_metrics(&baseline_metrics);
+        let b = mean_metrics(compare_metrics);
+        print_summary(name, &b, args.k);
+        println!(
+            "{:<16} P@{k} {:+.4}  MRR {:+.4}  nDCG@{k} {:+.4}",
+            "delta",
+            b.precision_at_k - a.precision_at_k,
+            b.reciprocal_rank - a.reciprocal_rank,
+            b.ndcg_at_k - a.ndcg_at_k,
+            k = args.k,
+        );
+    }
+}
+
+fn parse_args(args: Vec<String>) -> EvaluationArgs {
+    let mut queries_path = None;
+    let mut k = 10;
+    let mut profile = "default".to_string();
+    let mut compare = None;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--k" => {
+                k = match args.next().map(|value| value.parse()) {
+                    Some(Ok(value)) if value > 0 => value,
+                    _ => panic!("--k expects a positive integer\n{}", USAGE),
+                }
+            }
+            "--profile" => profile = args.next().expect(USAGE),
+            "--compare" => compare = Some(args.next().expect(USAGE)),
+            _ if queries_path.is_none() => queries_path = Some(arg),
+            _ => panic!("unexpected argument \"{}\"\n{}", arg, USAGE),
+        }
+    }
+
+    return EvaluationArgs {
+        queries_path: queries_path.expect(USAGE),
+        k,
+        profile,
+        compare,
+    };
+}
+
+async fn run_queries(
+    client: &Elasticsearch,
+    index: &str,
+    queries: &[EvaluationQuery],
+    languages: &[String],
+    profile: &RankingProfile,
+    k: usize,
+) -> Result<Vec<RankingMetrics>, PhotonError> {
+    let mut metrics = vec![];
+
+    for query in queries {
+        let returned = run_query(client, index, query, languages, profile, k).await?;
+        metrics.push(evaluate_ranking(&returned, &query.expected, k));
+    }
+
+    return Ok(metrics);
+}
+
+async fn run_query(
+    client: &Elasticsearch,
+    index: &str,
+    query: &EvaluationQuery,
+    languages: &[String],
+    profile: &RankingProfile,
+    k: usize,
+) -> Result<Vec<OsmRef>, PhotonError> {
+    let language = query.lang.clone().unwrap_or_else(|| "default".to_string());
+    let location_bias = validate_location_bias(&query.lon, &query.lat, &None, &None)?;
+
+    let mut result = None;
+    for lenient in [false, true] {
+        let search = build_search_query(
+            &query.q,
+            &language,
+            languages,
+            &lenient,
+            &QueryFilters::default(),
+            &location_bias,
+            profile,
+        );
+        let response = send_photon_query(
+            client,
+            index,
+            &Metrics::default(),
+            None,
+            search,
+            k as i64,
+            &language,
+        )
+        .await?;
+        if !response.features.is_empty() {
+            result = Some(response);
+            break;
+        }
+    }
+
+    return Ok(match result {
+        Some(response) => response
+            .features
+            .iter()
+            .map(|feature| OsmRef {
+                osm_type: feature.properties.osm_type.clone(),
+                osm_id: feature.properties.osm_id,
+            })
+            .collect(),
+        None => vec![],
+    });
+}
+
+fn print_summary(name: &str, metrics: &RankingMetrics, k: usize) {
+    println!(
+        "{:<16} P@{k} {:.4}  MRR {:.4}  nDCG@{k} {:.4}",
+        name,
+        metrics.precision_at_k,
+        metrics.reciprocal_rank,
+        metrics.ndcg_at_k,
+        k = k,
+    );
+}
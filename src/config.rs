@@ -1,9 +1,96 @@
+use axum::http::HeaderValue;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::query::RankingProfile;
+
 #[derive(Clone)]
 pub struct ApiConfig {
     pub host_address: String,
     pub host_port: String,
+    pub grpc_port: Option<String>,
     pub elastic_api_key: String,
     pub elastic_cloud_id: String,
+    pub index: String,
+    pub admin_api_key: Option<String>,
+    pub supported_database_versions: Option<Vec<String>>,
+    pub autocomplete_suggest_field: Option<String>,
+    pub lenient_search_mode: LenientSearchMode,
+    pub rate_limit: Option<RateLimitConfig>,
+    pub concurrency_limit: Option<ConcurrencyLimitConfig>,
+    pub http: HttpConfig,
+    pub tls: Option<TlsConfig>,
+}
+
+#[derive(Clone)]
+pub struct TlsConfig {
+    pub cert_path: String,
+    pub key_path: String,
+    pub reload_interval_secs: u64,
+}
+
+#[derive(Clone)]
+pub struct HttpConfig {
+    pub cors_allowed_origins: Option<Vec<HeaderValue>>,
+    pub compression: bool,
+    pub request_timeout_ms: u64,
+    pub route_timeouts_ms: HashMap<String, u64>,
+}
+
+// Routes ROUTE_TIMEOUTS_MS can configure, as registered in the router
+pub const TIMEOUT_ROUTES: [&str; 10] = [
+    "/search",
+    "/lookup",
+    "/reverse",
+    "/similar",
+    "/tiles/:z/:x/:y",
+    "/v1/search",
+    "/v1/autocomplete",
+    "/v1/reverse",
+    "/nominatim/search",
+    "/nominatim/reverse",
+];
+
+impl HttpConfig {
+    pub fn route_timeout(&self, path: &str) -> Duration {
+        debug_assert!(TIMEOUT_ROUTES.contains(&path));
+        let timeout_ms = match self.route_timeouts_ms.get(path) {
+            Some(timeout_ms) => *timeout_ms,
+            None => self.request_timeout_ms,
+        };
+        return Duration::from_millis(timeout_ms);
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct RateLimitConfig {
+    pub per_second: f64,
+    pub burst: f64,
+}
+
+#[derive(Clone, Copy)]
+pub struct ConcurrencyLimitConfig {
+    pub max_in_flight: usize,
+    pub max_queued: usize,
+    pub queue_timeout_ms: u64,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum LenientSearchMode {
+    Sequential,
+    Concurrent,
+    MultiSearch,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ApiKeyConfig {
+    pub key: String,
+    pub rate_limit: Option<u32>,
+    pub endpoints: Option<Vec<String>>,
+    pub max_limit: Option<i64>,
+    pub languages: Option<Vec<String>>,
 }
 
 #[derive(Clone)]
@@ -22,6 +109,8 @@ pub fn load_api_config() -> ApiConfig {
         _ => "2322".into(),
     };
 
+    let grpc_port = std::env::var("GRPC_PORT").ok();
+
     let elastic_api_key = match std::env::var("ELASTIC_API_KEY") {
         Ok(api_key) => api_key,
         _ => panic!("Environment variable `ELASTIC_API_KEY` should be set"),
@@ -32,24 +121,180 @@ pub fn load_api_config() -> ApiConfig {
         _ => panic!("Environment variable `ELASTIC_CLOUD_ID` should be set"),
     };
 
+    // Either an index name or an alias that can be switched with /admin/alias
+    let index = match std::env::var("PHOTON_INDEX") {
+        Ok(index) => index,
+        _ => "photon".into(),
+    };
+
+    let admin_api_key = std::env::var("ADMIN_API_KEY").ok();
+
+    let supported_database_versions = std::env::var("SUPPORTED_DATABASE_VERSIONS")
+        .ok()
+        .map(|versions| versions.split(',').map(|s| s.trim().into()).collect());
+
+    let autocomplete_suggest_field = std::env::var("AUTOCOMPLETE_SUGGEST_FIELD").ok();
+
+    let lenient_search_mode = match std::env::var("LENIENT_SEARCH_MODE") {
+        Ok(mode) => match mode.as_str() {
+            "sequential" => LenientSearchMode::Sequential,
+            "concurrent" => LenientSearchMode::Concurrent,
+            "msearch" => LenientSearchMode::MultiSearch,
+            _ => panic!(
+                "Invalid LENIENT_SEARCH_MODE: \"{}\". Allowed modes are [\"sequential\", \"concurrent\", \"msearch\"]",
+                mode
+            ),
+        },
+        _ => LenientSearchMode::Sequential,
+    };
+
+    let rate_limit = match std::env::var("RATE_LIMIT_PER_SECOND") {
+        Ok(value) => {
+            let per_second: f64 = parse_env("RATE_LIMIT_PER_SECOND", &value);
+            let burst: f64 = match std::env::var("RATE_LIMIT_BURST") {
+                Ok(value) => parse_env("RATE_LIMIT_BURST", &value),
+                _ => per_second.ceil(),
+            };
+            if per_second <= 0.0 || burst < 1.0 {
+                panic!(
+                    "RATE_LIMIT_PER_SECOND must be greater than 0 and RATE_LIMIT_BURST at least 1"
+                );
+            }
+            Some(RateLimitConfig { per_second, burst })
+        }
+        _ => None,
+    };
+
+    let concurrency_limit = match std::env::var("MAX_CONCURRENT_REQUESTS") {
+        Ok(value) => {
+            let max_in_flight: usize = parse_env("MAX_CONCURRENT_REQUESTS", &value);
+            let max_queued: usize = match std::env::var("MAX_QUEUED_REQUESTS") {
+                Ok(value) => parse_env("MAX_QUEUED_REQUESTS", &value),
+                _ => max_in_flight,
+            };
+            let queue_timeout_ms: u64 = match std::env::var("QUEUE_TIMEOUT_MS") {
+                Ok(value) => parse_env("QUEUE_TIMEOUT_MS", &value),
+                _ => 1000,
+            };
+            if max_in_flight == 0 {
+                panic!("MAX_CONCURRENT_REQUESTS must be greater than 0");
+            }
+            Some(ConcurrencyLimitConfig {
+                max_in_flight,
+                max_queued,
+                queue_timeout_ms,
+            })
+        }
+        _ => None,
+    };
+
+    let cors_allowed_origins = std::env::var("CORS_ALLOWED_ORIGINS").ok().map(|origins| {
+        origins
+            .split(',')
+            .map(|origin| match HeaderValue::from_str(origin.trim()) {
+                Ok(origin) => origin,
+                Err(_) => panic!("Invalid CORS_ALLOWED_ORIGINS origin: \"{}\"", origin),
+            })
+            .collect()
+    });
+
+    let compression = match std::env::var("COMPRESSION") {
+        Ok(value) => parse_env("COMPRESSION", &value),
+        _ => true,
+    };
+
+    let request_timeout_ms = match std::env::var("REQUEST_TIMEOUT_MS") {
+        Ok(value) => parse_env("REQUEST_TIMEOUT_MS", &value),
+        _ => 30000,
+    };
+
+    // "/search=2000,/tiles/:z/:x/:y=5000"
+    let route_timeouts_ms = match std::env::var("ROUTE_TIMEOUTS_MS") {
+        Ok(value) => value
+            .split(',')
+            .map(|route| match route.split_once('=') {
+                Some((path, _)) if !TIMEOUT_ROUTES.contains(&path.trim()) => panic!(
+                    "Invalid ROUTE_TIMEOUTS_MS path: \"{}\". Allowed paths are {:?}",
+                    path.trim(),
+                    TIMEOUT_ROUTES
+                ),
+                Some((path, timeout_ms)) => (
+                    path.trim().into(),
+                    parse_env("ROUTE_TIMEOUTS_MS", timeout_ms.trim()),
+                ),
+                None => panic!(
+                    "Invalid ROUTE_TIMEOUTS_MS entry: \"{}\". Expected \"<path>=<milliseconds>\"",
+                    route
+                ),
+            })
+            .collect(),
+        _ => HashMap::new(),
+    };
+
+    let tls = match (
+        std::env::var("TLS_CERT_PATH"),
+        std::env::var("TLS_KEY_PATH"),
+    ) {
+        (Ok(cert_path), Ok(key_path)) => {
+            let reload_interval_secs = match std::env::var("TLS_RELOAD_INTERVAL_SECS") {
+                Ok(value) => parse_env("TLS_RELOAD_INTERVAL_SECS", &value),
+                _ => 60,
+            };
+            if reload_interval_secs == 0 {
+                panic!("TLS_RELOAD_INTERVAL_SECS must be greater than 0");
+            }
+            Some(TlsConfig {
+                cert_path,
+                key_path,
+                reload_interval_secs,
+            })
+        }
+        (Err(_), Err(_)) => None,
+        _ => {
+            panic!("Environment variables `TLS_CERT_PATH` and `TLS_KEY_PATH` must be set together")
+        }
+    };
+
     return ApiConfig {
         host_address,
         host_port,
+        grpc_port,
         elastic_api_key,
         elastic_cloud_id,
+        index,
+        admin_api_key,
+        supported_database_versions,
+        autocomplete_suggest_field,
+        lenient_search_mode,
+        rate_limit,
+        concurrency_limit,
+        http: HttpConfig {
+            cors_allowed_origins,
+            compression,
+            request_timeout_ms,
+            route_timeouts_ms,
+        },
+        tls,
+    };
+}
+
+fn parse_env<T: std::str::FromStr>(name: &str, value: &str) -> T {
+    return match value.parse() {
+        Ok(value) => value,
+        Err(_) => panic!("Invalid {}: \"{}\"", name, value),
     };
 }
 
 pub fn load_language_config() -> Vec<String> {
     let all_valid_languages = vec!["en".into(), "de".into(), "fr".into(), "it".into()];
 
-    let valid_languages = match std::env::var("VALID_LANGUAGES") {
+    let valid_languages: Vec<String> = match std::env::var("VALID_LANGUAGES") {
         Ok(languages) => languages.split(",").map(|s| s.into()).collect(),
         _ => all_valid_languages.clone(),
     };
 
     for language in &valid_languages {
-        if !all_valid_languages.contains(&language) {
+        if !all_valid_languages.contains(language) {
             panic!(
                 "Invalid language specified in VALID_LANGUAGES: \"{}\". Allowed languages are {:?}",
                 language, all_valid_languages
@@ -59,3 +304,110 @@ pub fn load_language_config() -> Vec<String> {
 
     return valid_languages;
 }
+
+pub fn load_ranking_config() -> HashMap<String, RankingProfile> {
+    let mut profiles: HashMap<String, RankingProfile> = HashMap::from([
+        ("default".into(), RankingProfile::default()),
+        (
+            "autocomplete".into(),
+            RankingProfile {
+                name_ngram_boost: 3.0,
+                location_bias_decay: 0.6,
+                ..RankingProfile::default()
+            },
+        ),
+        (
+            "address".into(),
+            RankingProfile {
+                housenumber_weight: 20.0,
+                housenumber_boost: 0.6,
+                classification_weight: 0.0,
+                ..RankingProfile::default()
+            },
+        ),
+        (
+            "poi".into(),
+            RankingProfile {
+                classification_weight: 0.5,
+                importance_scale: 0.8,
+                ..RankingProfile::default()
+            },
+        ),
+    ]);
+
+    if let Ok(path) = std::env::var("RANKING_PROFILES") {
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) => panic!("Could not read RANKING_PROFILES file \"{}\": {}", path, err),
+        };
+
+        let configured: HashMap<String, RankingProfile> = match serde_json::from_str(&contents) {
+            Ok(configured) => configured,
+            Err(err) => panic!("Invalid RANKING_PROFILES file \"{}\": {}", path, err),
+        };
+
+        profiles.extend(configured);
+    }
+
+    for (name, profile) in &profiles {
+        if profile.location_bias_radius_divisor == 0 || profile.location_bias_offset_divisor == 0 {
+            panic!(
+                "Invalid ranking profile \"{}\": location bias divisors must be greater than 0",
+                name
+            );
+        }
+    }
+
+    return profiles;
+}
+
+pub fn load_api_key_config() -> HashMap<String, ApiKeyConfig> {
+    let mut keys: HashMap<String, ApiKeyConfig> = HashMap::new();
+
+    if let Ok(values) = std::env::var("API_KEYS") {
+        for (index, key) in values.split(',').map(|key| key.trim()).enumerate() {
+            keys.insert(
+                format!("api-key-{}", index + 1),
+                ApiKeyConfig {
+                    key: key.into(),
+                    ..ApiKeyConfig::default()
+                },
+            );
+        }
+    }
+
+    if let Ok(path) = std::env::var("API_KEYS_FILE") {
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) => panic!("Could not read API_KEYS_FILE file \"{}\": {}", path, err),
+        };
+
+        let configured: HashMap<String, ApiKeyConfig> = match serde_json::from_str(&contents) {
+            Ok(configured) => configured,
+            Err(err) => panic!("Invalid API_KEYS_FILE file \"{}\": {}", path, err),
+        };
+
+        keys.extend(configured);
+    }
+
+    let mut names: HashMap<&str, &str> = HashMap::new();
+    for (name, config) in &keys {
+        if config.key.trim().is_empty() {
+            panic!("Invalid API key \"{}\": key must not be empty", name);
+        }
+        if let Some(other) = names.insert(config.key.as_str(), name.as_str()) {
+            panic!(
+                "Invalid API key \"{}\": key is already configured for \"{}\"",
+                name, other
+            );
+        }
+        if config.rate_limit == Some(0) {
+            panic!(
+                "Invalid API key \"{}\": rate_limit must be greater than 0",
+                name
+            );
+        }
+    }
+
+    return keys;
+}
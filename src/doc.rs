@@ -1,5 +1,13 @@
-use crate::response::{PhotonGeometry, PhotonResponseFeature, PhotonResponseProperties};
-use serde::Deserialize;
+use crate::address_format::format_address;
+use crate::query::{
+    CLASSIFICATION_QUERY, COLLECTOR_RAW_QUERY, FIELDS_QUERY, FULL_NAME_QUERY, HOUSENUMBER_QUERY,
+    NAME_NGRAM_QUERY, PREFIX_QUERY,
+};
+use crate::response::{
+    FullGeometry, MatchType, PhotonGeometry, PhotonResponseFeature, PhotonResponseProperties,
+};
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
 use std::collections::HashMap;
 
 type LanguageField = HashMap<String, String>;
@@ -30,6 +38,8 @@ pub struct PhotonDocument {
     pub name: Option<LanguageField>,
     pub names: Option<HashMap<String, String>>,
     pub extra: Option<HashMap<String, String>>,
+    // Typed so a geometry that can not be read skips the document like any other invalid field
+    pub geometry: Option<FullGeometry>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -49,66 +59,202 @@ pub struct ElasticsearchResponse {
     pub hits: ElasticsearchHits,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ElasticsearchMultiResponse {
+    pub responses: Vec<ElasticsearchMultiItem>,
+}
+
+// Searches of a msearch request fail individually, those carry an error instead of hits
+#[derive(Debug, Deserialize)]
+pub struct ElasticsearchMultiItem {
+    pub hits: Option<ElasticsearchHits>,
+    pub error: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ElasticsearchCountResponse {
+    pub count: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ElasticsearchFieldMappings {
+    pub mappings: HashMap<String, Value>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ElasticsearchHits {
     pub hits: Vec<ElasticsearchHit>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ElasticsearchSuggestResponse {
+    pub suggest: HashMap<String, Vec<ElasticsearchSuggestion>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ElasticsearchSuggestion {
+    pub options: Vec<ElasticsearchHit>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ElasticsearchHit {
-    pub _source: Option<PhotonDocument>,
+    pub _id: Option<String>,
+    pub _source: Option<SourceDocument>,
+    pub _score: Option<f64>,
+    pub matched_queries: Option<Vec<String>>,
+    pub highlight: Option<HashMap<String, Vec<String>>>,
 }
 
-pub fn document_to_feature(doc: &PhotonDocument, language: &String) -> PhotonResponseFeature {
+// Mapping changes must not fail the whole response, so every `_source` is read on its own
+#[derive(Debug)]
+pub enum SourceDocument {
+    Valid(Box<PhotonDocument>),
+    Invalid(String),
+}
+
+impl<'de> Deserialize<'de> for SourceDocument {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = Value::deserialize(deserializer)?;
+        return Ok(match serde_json::from_value(value) {
+            Ok(document) => SourceDocument::Valid(Box::new(document)),
+            Err(err) => SourceDocument::Invalid(err.to_string()),
+        });
+    }
+}
+
+// Named queries a hit can match, from the most to the least specific
+const MATCH_TYPES: [(&str, MatchType); 7] = [
+    (FULL_NAME_QUERY, MatchType::Exact),
+    (HOUSENUMBER_QUERY, MatchType::Housenumber),
+    (COLLECTOR_RAW_QUERY, MatchType::Address),
+    (NAME_NGRAM_QUERY, MatchType::Name),
+    (PREFIX_QUERY, MatchType::Prefix),
+    (FIELDS_QUERY, MatchType::Partial),
+    (CLASSIFICATION_QUERY, MatchType::Classification),
+];
+
+pub fn hit_to_feature(hit: &ElasticsearchHit, language: &str) -> Option<PhotonResponseFeature> {
+    let document = match hit._source.as_ref()? {
+        SourceDocument::Valid(document) => document,
+        SourceDocument::Invalid(_) => return None,
+    };
+    let mut feature = document_to_feature(document, language);
+
+    feature.properties.score = hit._score;
+    feature.properties.match_type = hit.matched_queries.as_ref().and_then(|matched| {
+        MATCH_TYPES
+            .iter()
+            .find(|(name, _)| matched.iter().any(|query| query == name))
+            .map(|(_, match_type)| *match_type)
+    });
+    feature.properties.matched_fields = hit.matched_queries.clone();
+    feature.properties.highlight = hit.highlight.as_ref().map(|highlight| {
+        highlight
+            .iter()
+            .map(|(field, fragments)| {
+                let name = field.split('.').next().unwrap_or(field);
+                (name.to_string(), fragments.clone())
+            })
+            .collect()
+    });
+
+    return Some(feature);
+}
+
+pub fn document_to_feature(doc: &PhotonDocument, language: &str) -> PhotonResponseFeature {
+    let mut properties = PhotonResponseProperties {
+        parent_place_id: doc.parent_place_id,
+        place_id: doc.place_id,
+        osm_type: doc.osm_type.clone(),
+        osm_id: doc.osm_id,
+        osm_key: doc.osm_key.clone(),
+        osm_value: doc.osm_value.clone(),
+        r#type: doc.r#type.clone(),
+        postcode: doc.postcode.clone(),
+        housenumber: doc.housenumber.clone(),
+        countrycode: doc.countrycode.clone(),
+        name: unwrap_language_field(&doc.name, language),
+        country: unwrap_language_field(&doc.country, language),
+        city: unwrap_language_field(&doc.city, language),
+        district: unwrap_language_field(&doc.district, language),
+        locality: unwrap_language_field(&doc.locality, language),
+        street: unwrap_language_field(&doc.street, language),
+        state: unwrap_language_field(&doc.state, language),
+        county: unwrap_language_field(&doc.county, language),
+        formatted: None,
+        extra: doc.extra.clone(),
+        names: doc.names.clone(),
+        extent: doc.extent.as_ref().map(|extent| {
+            [
+                extent.coordinates[0][0],
+                extent.coordinates[0][1],
+                extent.coordinates[1][0],
+                extent.coordinates[1][1],
+            ]
+        }),
+        geometry: PhotonGeometry {
+            r#type: "Point".to_string(),
+            coordinates: [doc.coordinate.lon, doc.coordinate.lat],
+        },
+        full_geometry: doc.geometry.clone(),
+        score: None,
+        matched_fields: None,
+        match_type: None,
+        highlight: None,
+    };
+    properties.formatted = format_address(&properties);
+
     return PhotonResponseFeature {
         r#type: "Feature".to_string(),
-        properties: PhotonResponseProperties {
-            parent_place_id: doc.parent_place_id,
-            place_id: doc.place_id,
-            osm_type: doc.osm_type.clone(),
-            osm_id: doc.osm_id,
-            osm_key: doc.osm_key.clone(),
-            osm_value: doc.osm_value.clone(),
-            r#type: doc.r#type.clone(),
-            postcode: doc.postcode.clone(),
-            housenumber: doc.housenumber.clone(),
-            countrycode: doc.countrycode.clone(),
-            name: unwrap_language_field(&doc.name, language),
-            country: unwrap_language_field(&doc.country, language),
-            city: unwrap_language_field(&doc.city, language),
-            district: unwrap_language_field(&doc.district, language),
-            locality: unwrap_language_field(&doc.locality, language),
-            street: unwrap_language_field(&doc.street, language),
-            state: unwrap_language_field(&doc.state, language),
-            county: unwrap_language_field(&doc.county, language),
-            extra: doc.extra.clone(),
-            names: doc.names.clone(),
-            extent: match &doc.extent {
-                Some(extent) => Some([
-                    extent.coordinates[0][0],
-                    extent.coordinates[0][1],
-                    extent.coordinates[1][0],
-                    extent.coordinates[1][1],
-                ]),
-                None => None,
-            },
-            geometry: PhotonGeometry {
-                r#type: "Point".to_string(),
-                coordinates: [doc.coordinate.lon, doc.coordinate.lat],
-            },
-        },
+        properties,
     };
 }
 
-fn unwrap_language_field(field: &Option<LanguageField>, language: &String) -> Option<String> {
+fn unwrap_language_field(field: &Option<LanguageField>, language: &str) -> Option<String> {
     return match field {
         Some(field) => match field.get(language) {
             Some(value) => Some(value.clone()),
-            None => match field.get("default") {
-                Some(value) => Some(value.clone()),
-                None => None,
-            },
+            None => field.get("default").cloned(),
         },
         None => None,
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn source(geometry: Value) -> SourceDocument {
+        return serde_json::from_value(json!({
+            "type": "house",
+            "importance": 0.5,
+            "place_id": 1,
+            "osm_id": 42,
+            "osm_type": "W",
+            "osm_key": "building",
+            "osm_value": "yes",
+            "coordinate": {"lat": 52.5, "lon": 13.4},
+            "geometry": geometry,
+        }))
+        .unwrap();
+    }
+
+    #[test]
+    fn reads_geometry() {
+        let document = match source(json!({"type": "Point", "coordinates": [13.4, 52.5]})) {
+            SourceDocument::Valid(document) => document,
+            SourceDocument::Invalid(error) => panic!("invalid document: {}", error),
+        };
+        assert_eq!(document.geometry, Some(FullGeometry::Point([13.4, 52.5])));
+    }
+
+    #[test]
+    fn unreadable_geometry_invalidates_document() {
+        let geometry = json!({"type": "GeometryCollection", "geometries": []});
+        assert!(matches!(source(geometry), SourceDocument::Invalid(_)));
+    }
+}
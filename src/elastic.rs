@@ -1,64 +1,358 @@
+use elasticsearch::http::headers::{HeaderValue, AUTHORIZATION};
+use elasticsearch::http::request::JsonBody;
+use elasticsearch::http::transport::{CloudConnectionPool, TransportBuilder};
+use elasticsearch::http::StatusCode;
+use elasticsearch::indices::{
+    IndicesExistsParts, IndicesGetAliasParts, IndicesGetFieldMappingParts,
+};
 use elasticsearch::params::SearchType;
-use elasticsearch::{Elasticsearch, GetParts, SearchParts};
+use elasticsearch::{CountParts, Elasticsearch, GetParts, MsearchParts, SearchParts};
 use elasticsearch_dsl::Search;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tokio::sync::OwnedSemaphorePermit;
 
-use crate::doc::{document_to_feature, ElasticsearchHit, ElasticsearchResponse};
+use crate::doc::{
+    document_to_feature, hit_to_feature, ElasticsearchCountResponse, ElasticsearchFieldMappings,
+    ElasticsearchHit, ElasticsearchMultiResponse, ElasticsearchResponse,
+    ElasticsearchSuggestResponse, PhotonDocument, SourceDocument,
+};
 use crate::errors::PhotonError;
-use crate::response::{PhotonResponse, PhotonResponseFeature};
+use crate::limits::ConcurrencyLimiter;
+use crate::metrics::Metrics;
+use crate::query::{exclude_geometry_by_default, AUTOCOMPLETE_SUGGESTER, GEOMETRY_FIELD};
+use crate::response::{PhotonResponse, PhotonResponseFeature, SkippedDocument};
+use crate::schema::{DATABASE_PROPERTIES_ID, DATABASE_VERSION_FIELD};
 
-const PHOTON_INDEX: &'static str = "photon";
+const SKIPPED_DOCUMENTS_METRIC: &str = "photon_skipped_documents_total";
+
+pub fn create_elasticsearch_client(
+    cloud_id: &str,
+    api_key: &str,
+) -> Result<Elasticsearch, PhotonError> {
+    let mut api_key_header: HeaderValue =
+        HeaderValue::from_str(&format!("ApiKey {}", api_key)).unwrap();
+    api_key_header.set_sensitive(true);
+
+    // NB: we may want to implement our own connection pooling, as both
+    // SingleNodeConnectionPool and CloudConnectionPool use a single connection
+    let conn_pool = CloudConnectionPool::new(cloud_id)?;
+    let transport = TransportBuilder::new(conn_pool)
+        .header(AUTHORIZATION, api_key_header)
+        .build()
+        .unwrap();
+
+    return Ok(Elasticsearch::new(transport));
+}
 
 pub async fn send_photon_query(
     client: &Elasticsearch,
+    index: &str,
+    metrics: &Metrics,
+    limiter: Option<&ConcurrencyLimiter>,
     query: Search,
     size: i64,
-    language: &String,
+    language: &str,
 ) -> Result<PhotonResponse, PhotonError> {
+    let _permit = acquire_permit(limiter).await?;
     let response: ElasticsearchResponse = client
-        .search(SearchParts::Index(&[PHOTON_INDEX]))
+        .search(SearchParts::Index(&[index]))
         .search_type(SearchType::QueryThenFetch)
         .size(size)
-        .body(query)
+        .body(exclude_geometry_by_default(query))
         .send()
         .await?
         .json()
         .await?;
 
-    let features: Vec<PhotonResponseFeature> = response
-        .hits
-        .hits
+    let hits: Vec<&ElasticsearchHit> = response.hits.hits.iter().collect();
+    return Ok(hits_to_photon_response(&hits, language, index, metrics));
+}
+
+pub async fn send_photon_multi_query(
+    client: &Elasticsearch,
+    index: &str,
+    metrics: &Metrics,
+    limiter: Option<&ConcurrencyLimiter>,
+    queries: Vec<Search>,
+    size: i64,
+    language: &str,
+) -> Result<Vec<Option<PhotonResponse>>, PhotonError> {
+    let mut body: Vec<JsonBody<Value>> = vec![];
+    for query in queries {
+        body.push(json!({}).into());
+        body.push(exclude_geometry_by_default(query.size(size.max(0) as u64)).into());
+    }
+
+    let _permit = acquire_permit(limiter).await?;
+    let response: ElasticsearchMultiResponse = client
+        .msearch(MsearchParts::Index(&[index]))
+        .search_type(SearchType::QueryThenFetch)
+        .body(body)
+        .send()
+        .await?
+        .json()
+        .await?;
+
+    return Ok(response
+        .responses
         .iter()
-        .filter(|hit| hit._source.is_some())
-        .map(|hit| document_to_feature(&hit._source.as_ref().unwrap(), &language))
+        .map(|response| match &response.hits {
+            Some(hits) => {
+                let hits: Vec<&ElasticsearchHit> = hits.hits.iter().collect();
+                Some(hits_to_photon_response(&hits, language, index, metrics))
+            }
+            None => {
+                println!(
+                    "Search in index \"{}\" failed: {}",
+                    index,
+                    response.error.clone().unwrap_or_default()
+                );
+                None
+            }
+        })
+        .collect());
+}
+
+// Every Elasticsearch round trip holds a slot, so batches, sessions and lenient retries are capped too
+async fn acquire_permit(
+    limiter: Option<&ConcurrencyLimiter>,
+) -> Result<Option<OwnedSemaphorePermit>, PhotonError> {
+    return match limiter {
+        Some(limiter) => Ok(Some(limiter.acquire().await?)),
+        None => Ok(None),
+    };
+}
+
+fn hits_to_photon_response(
+    hits: &[&ElasticsearchHit],
+    language: &str,
+    index: &str,
+    metrics: &Metrics,
+) -> PhotonResponse {
+    let features: Vec<PhotonResponseFeature> = hits
+        .iter()
+        .filter_map(|hit| hit_to_feature(hit, language))
         .collect();
 
-    let photon_response = PhotonResponse {
+    let skipped: Vec<SkippedDocument> = hits
+        .iter()
+        .filter_map(|hit| match &hit._source {
+            Some(SourceDocument::Invalid(error)) => {
+                let id = hit._id.clone().unwrap_or_default();
+                Some(skip_document(id, error, index, metrics))
+            }
+            _ => None,
+        })
+        .collect();
+
+    return PhotonResponse {
         r#type: "FeatureCollection".to_string(),
         features,
+        lenient: false,
+        skipped,
+        debug: None,
     };
+}
 
-    return Ok(photon_response);
+fn skip_document(id: String, error: &str, index: &str, metrics: &Metrics) -> SkippedDocument {
+    println!(
+        "Skipped document \"{}\" in index \"{}\": {}",
+        id, index, error
+    );
+    metrics.increment(SKIPPED_DOCUMENTS_METRIC, &[("index", index)]);
+
+    return SkippedDocument {
+        id,
+        error: error.to_string(),
+    };
 }
 
-pub async fn send_lookup(
+pub async fn send_photon_suggest(
     client: &Elasticsearch,
-    place_id: &String,
-    language: &String,
-) -> Result<axum::Json<PhotonResponse>, PhotonError> {
-    let response: ElasticsearchHit = client
-        .get(GetParts::IndexId(PHOTON_INDEX, place_id))
+    index: &str,
+    metrics: &Metrics,
+    limiter: Option<&ConcurrencyLimiter>,
+    query: Search,
+    language: &str,
+) -> Result<PhotonResponse, PhotonError> {
+    let _permit = acquire_permit(limiter).await?;
+    let response: ElasticsearchSuggestResponse = client
+        .search(SearchParts::Index(&[index]))
+        .body(exclude_geometry_by_default(query))
         .send()
         .await?
         .json()
         .await?;
 
+    let options: Vec<&ElasticsearchHit> = match response.suggest.get(AUTOCOMPLETE_SUGGESTER) {
+        Some(suggestions) => suggestions
+            .iter()
+            .flat_map(|suggestion| suggestion.options.iter())
+            .collect(),
+        None => vec![],
+    };
+
+    return Ok(hits_to_photon_response(&options, language, index, metrics));
+}
+
+pub async fn send_lookup(
+    client: &Elasticsearch,
+    index: &str,
+    metrics: &Metrics,
+    limiter: Option<&ConcurrencyLimiter>,
+    place_id: &str,
+    language: &str,
+    full_geometry: &bool,
+) -> Result<PhotonResponse, PhotonError> {
+    let document = fetch_document(client, index, metrics, limiter, place_id, full_geometry).await?;
+
     let photon_response = PhotonResponse {
         r#type: "FeatureCollection".to_string(),
-        features: match response._source {
-            Some(source) => vec![document_to_feature(&source, &language)],
+        features: match document {
+            Some(source) => vec![document_to_feature(&source, language)],
             None => vec![],
         },
+        lenient: false,
+        skipped: vec![],
+        debug: None,
     };
 
-    return Ok(axum::Json::from(photon_response));
+    return Ok(photon_response);
+}
+
+pub async fn fetch_document(
+    client: &Elasticsearch,
+    index: &str,
+    metrics: &Metrics,
+    limiter: Option<&ConcurrencyLimiter>,
+    place_id: &str,
+    full_geometry: &bool,
+) -> Result<Option<PhotonDocument>, PhotonError> {
+    let mut request = client.get(GetParts::IndexId(index, place_id));
+    if !*full_geometry {
+        request = request._source_excludes(&[GEOMETRY_FIELD]);
+    }
+
+    let _permit = acquire_permit(limiter).await?;
+    let response: ElasticsearchHit = request.send().await?.json().await?;
+
+    return Ok(match response._source {
+        Some(SourceDocument::Valid(document)) => Some(*document),
+        Some(SourceDocument::Invalid(error)) => {
+            skip_document(place_id.to_string(), &error, index, metrics);
+            None
+        }
+        None => None,
+    });
+}
+
+pub async fn count_documents(client: &Elasticsearch, index: &str) -> Result<u64, PhotonError> {
+    let response: ElasticsearchCountResponse = client
+        .count(CountParts::Index(&[index]))
+        .send()
+        .await?
+        .error_for_status_code()?
+        .json()
+        .await?;
+
+    return Ok(response.count);
+}
+
+pub async fn fetch_alias_indexes(
+    client: &Elasticsearch,
+    alias: &str,
+) -> Result<Vec<String>, PhotonError> {
+    let response = client
+        .indices()
+        .get_alias(IndicesGetAliasParts::Name(&[alias]))
+        .send()
+        .await?;
+
+    if response.status_code() == StatusCode::NOT_FOUND {
+        return Ok(vec![]);
+    }
+
+    let indexes: HashMap<String, Value> = response.error_for_status_code()?.json().await?;
+    let mut indexes: Vec<String> = indexes.into_keys().collect();
+    indexes.sort();
+
+    return Ok(indexes);
+}
+
+pub async fn index_exists(client: &Elasticsearch, index: &str) -> Result<bool, PhotonError> {
+    let response = client
+        .indices()
+        .exists(IndicesExistsParts::Index(&[index]))
+        .send()
+        .await?;
+
+    return Ok(response.status_code() == StatusCode::OK);
+}
+
+// Both actions are applied in one request, so searches never see the alias missing
+pub async fn swap_alias(
+    client: &Elasticsearch,
+    alias: &str,
+    previous: &[String],
+    index: &str,
+) -> Result<(), PhotonError> {
+    let mut actions: Vec<Value> = previous
+        .iter()
+        .map(|previous| json!({ "remove": { "index": previous, "alias": alias } }))
+        .collect();
+    actions.push(json!({ "add": { "index": index, "alias": alias } }));
+
+    client
+        .indices()
+        .update_aliases()
+        .body(json!({ "actions": actions }))
+        .send()
+        .await?
+        .error_for_status_code()?;
+
+    return Ok(());
+}
+
+// Returns the requested fields that exist, per concrete index behind `index`
+pub async fn fetch_field_mappings(
+    client: &Elasticsearch,
+    index: &str,
+    fields: &[&str],
+) -> Result<HashMap<String, Vec<String>>, PhotonError> {
+    let response: HashMap<String, ElasticsearchFieldMappings> = client
+        .indices()
+        .get_field_mapping(IndicesGetFieldMappingParts::IndexFields(&[index], fields))
+        .send()
+        .await?
+        .error_for_status_code()?
+        .json()
+        .await?;
+
+    return Ok(response
+        .into_iter()
+        .map(|(index, mappings)| (index, mappings.mappings.into_keys().collect()))
+        .collect());
+}
+
+pub async fn fetch_database_version(
+    client: &Elasticsearch,
+    index: &str,
+) -> Result<Option<String>, PhotonError> {
+    let response = client
+        .get(GetParts::IndexId(index, DATABASE_PROPERTIES_ID))
+        .send()
+        .await?;
+
+    // Without the properties document the index has no version, check_index_schema reports
+    // that as SchemaError::MissingVersion
+    if response.status_code() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+
+    let response: Value = response.error_for_status_code()?.json().await?;
+
+    return Ok(response["_source"][DATABASE_VERSION_FIELD]
+        .as_str()
+        .map(|version| version.to_string()));
 }
@@ -1,3 +1,4 @@
+use axum::http::header::RETRY_AFTER;
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use std::error::Error;
@@ -9,23 +10,75 @@ type ElasticsearchError = elasticsearch::Error;
 #[derive(Debug)]
 pub enum PhotonError {
     Validation(ValidationError),
+    Auth(AuthError),
+    Limit(LimitError),
     Elasticsearch(ElasticsearchError),
 }
 
+#[derive(Debug)]
+pub enum AuthError {
+    MissingKey,
+    InvalidKey,
+    Endpoint(String),
+    Limit { value: i64, max: i64 },
+    Lang { value: String, valid: Vec<String> },
+    RateLimited { retry_after: u64 },
+}
+
+#[derive(Debug)]
+pub enum LimitError {
+    RateLimited { retry_after: u64 },
+    QueueFull { retry_after: u64 },
+    QueueTimeout { retry_after: u64 },
+    Timeout,
+}
+
+#[derive(Debug)]
+pub enum SchemaError {
+    Elasticsearch(PhotonError),
+    MissingVersion,
+    Version {
+        value: String,
+        supported: Vec<String>,
+    },
+    MissingFields {
+        index: String,
+        fields: Vec<String>,
+    },
+}
+
 #[derive(Debug)]
 pub enum ValidationError {
     Lon(f32),
     Lat(f32),
     Bbox([f32; 4]),
+    BboxLength(usize),
+    Viewbox(String),
+    BoundaryRect,
+    Country(String),
     Layer { value: String, valid: Vec<String> },
     Lang { value: String, valid: Vec<String> },
+    Profile { value: String, valid: Vec<String> },
     LocationBias,
+    MissingQuery,
+    EmptyText,
+    Radius,
+    RadiusRange { value: u64, max: u64 },
+    Limit { value: i64, max: i64 },
+    GeometryTolerance(f64),
+    Tile(String),
+    DocumentCount { index: String, count: u64, min: u64 },
+    MaxCountDrop(f64),
+    Schema(String),
+    ConcreteIndex(String),
 }
 
 impl IntoResponse for PhotonError {
     fn into_response(self) -> Response {
         match self {
             PhotonError::Validation(err) => err.into_response(),
+            PhotonError::Auth(err) => err.into_response(),
+            PhotonError::Limit(err) => err.into_response(),
             PhotonError::Elasticsearch(err) => (
                 match err.status_code() {
                     // elasticsearch::http::StatusCode is incompatible with axum::http::StatusCode
@@ -40,6 +93,146 @@ impl IntoResponse for PhotonError {
     }
 }
 
+impl From<PhotonError> for tonic::Status {
+    fn from(value: PhotonError) -> Self {
+        return match value {
+            PhotonError::Validation(err) => tonic::Status::invalid_argument(err.to_string()),
+            PhotonError::Auth(err) => tonic::Status::from(err),
+            PhotonError::Limit(err) => tonic::Status::from(err),
+            PhotonError::Elasticsearch(err) => tonic::Status::internal(err.to_string()),
+        };
+    }
+}
+
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        return match self {
+            AuthError::MissingKey | AuthError::InvalidKey => {
+                (StatusCode::UNAUTHORIZED, self.to_string()).into_response()
+            }
+            AuthError::RateLimited { retry_after } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(RETRY_AFTER, retry_after.to_string())],
+                self.to_string(),
+            )
+                .into_response(),
+            _ => (StatusCode::FORBIDDEN, self.to_string()).into_response(),
+        };
+    }
+}
+
+impl From<AuthError> for tonic::Status {
+    fn from(value: AuthError) -> Self {
+        return match value {
+            AuthError::MissingKey | AuthError::InvalidKey => {
+                tonic::Status::unauthenticated(value.to_string())
+            }
+            AuthError::RateLimited { .. } => tonic::Status::resource_exhausted(value.to_string()),
+            _ => tonic::Status::permission_denied(value.to_string()),
+        };
+    }
+}
+
+impl From<LimitError> for tonic::Status {
+    fn from(value: LimitError) -> Self {
+        return match value {
+            LimitError::RateLimited { .. } => tonic::Status::resource_exhausted(value.to_string()),
+            LimitError::Timeout => tonic::Status::deadline_exceeded(value.to_string()),
+            _ => tonic::Status::unavailable(value.to_string()),
+        };
+    }
+}
+
+impl fmt::Display for PhotonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            PhotonError::Validation(err) => write!(f, "{err}"),
+            PhotonError::Auth(err) => write!(f, "{err}"),
+            PhotonError::Limit(err) => write!(f, "{err}"),
+            PhotonError::Elasticsearch(err) => write!(f, "{err}"),
+        };
+    }
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            AuthError::MissingKey => write!(f, "missing API key. Use the \"X-API-Key\" header or the \"api_key\" parameter"),
+            AuthError::InvalidKey => write!(f, "invalid API key"),
+            AuthError::Endpoint(value) => write!(f, "API key is not allowed to use \"{value}\""),
+            AuthError::Limit{value, max} => write!(f, "limit \"{value:?}\" exceeds the maximum of {max} allowed for this API key"),
+            AuthError::Lang{value, valid} => write!(f, "language \"{value:?}\" is not allowed for this API key. Allowed languages are {valid:?}"),
+            AuthError::RateLimited{retry_after} => write!(f, "rate limit exceeded for this API key. Retry after {retry_after} seconds")
+        };
+    }
+}
+
+impl Error for AuthError {}
+
+impl IntoResponse for LimitError {
+    fn into_response(self) -> Response {
+        let (status, retry_after) = match self {
+            LimitError::RateLimited { retry_after } => (StatusCode::TOO_MANY_REQUESTS, retry_after),
+            LimitError::QueueFull { retry_after } | LimitError::QueueTimeout { retry_after } => {
+                (StatusCode::SERVICE_UNAVAILABLE, retry_after)
+            }
+            LimitError::Timeout => {
+                return (StatusCode::GATEWAY_TIMEOUT, self.to_string()).into_response();
+            }
+        };
+        return (
+            status,
+            [(RETRY_AFTER, retry_after.to_string())],
+            self.to_string(),
+        )
+            .into_response();
+    }
+}
+
+impl fmt::Display for LimitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            LimitError::RateLimited { retry_after } => {
+                write!(f, "too many requests. Retry after {retry_after} seconds")
+            }
+            LimitError::QueueFull { retry_after } => {
+                write!(f, "server is overloaded. Retry after {retry_after} seconds")
+            }
+            LimitError::QueueTimeout { retry_after } => write!(
+                f,
+                "timed out waiting for a free slot. Retry after {retry_after} seconds"
+            ),
+            LimitError::Timeout => write!(f, "request timed out"),
+        };
+    }
+}
+
+impl Error for LimitError {}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            SchemaError::Elasticsearch(err) => write!(f, "{err}"),
+            SchemaError::MissingVersion => write!(f, "index has no database version"),
+            SchemaError::Version { value, supported } => write!(
+                f,
+                "unsupported database version \"{value}\". Supported versions are {supported:?}"
+            ),
+            SchemaError::MissingFields { index, fields } => {
+                write!(f, "index \"{index}\" is missing the fields {fields:?}")
+            }
+        };
+    }
+}
+
+impl Error for SchemaError {}
+
+impl From<PhotonError> for SchemaError {
+    fn from(value: PhotonError) -> Self {
+        return SchemaError::Elasticsearch(value);
+    }
+}
+
 impl IntoResponse for ValidationError {
     fn into_response(self) -> Response {
         return (StatusCode::BAD_REQUEST, self.to_string()).into_response();
@@ -52,9 +245,25 @@ impl fmt::Display for ValidationError {
             ValidationError::Lon(value) => write!(f, "invalid lon \"{value:?}\". Must be in the range [-180, 180]"),
             ValidationError::Lat(value) => write!(f, "invalid lat \"{value:?}\". Must be in the range [-90, 90]"),
             ValidationError::Bbox(value) => write!(f, "invalid bbox \"{value:?}\". Expected \"min_lon,min_lat,max_lon,max_lat\" where \"lat\" is in range [-90, 90] and \"lon\" is in range [-180, 180]"),
+            ValidationError::BboxLength(value) => write!(f, "invalid bbox with {value} values. Expected \"min_lon,min_lat,max_lon,max_lat\""),
+            ValidationError::Viewbox(value) => write!(f, "invalid viewbox \"{value:?}\". Expected \"x1,y1,x2,y2\""),
+            ValidationError::BoundaryRect => write!(f, "must use all or none of boundary.rect.min_lon, boundary.rect.min_lat, boundary.rect.max_lon, boundary.rect.max_lat"),
+            ValidationError::Country(value) => write!(f, "invalid country \"{value:?}\". Expected ISO 3166-1 alpha-2 or alpha-3 country codes"),
             ValidationError::Layer{value, valid} => write!(f, "invalid layer \"{value:?}\". Allowed layers are {valid:?}"),
             ValidationError::Lang{value, valid} => write!(f, "invalid language \"{value:?}\". Allowed languages are {valid:?}"),
-            ValidationError::LocationBias => write!(f, "must use both or neither of lon, lat")
+            ValidationError::Profile{value, valid} => write!(f, "invalid profile \"{value:?}\". Allowed profiles are {valid:?}"),
+            ValidationError::LocationBias => write!(f, "must use both or neither of lon, lat"),
+            ValidationError::MissingQuery => write!(f, "must use q, or at least one of osm_tag, layer when q is omitted"),
+            ValidationError::EmptyText => write!(f, "must use a non-empty text"),
+            ValidationError::Radius => write!(f, "radius requires both lon and lat"),
+            ValidationError::RadiusRange{value, max} => write!(f, "invalid radius \"{value:?}\". Must be in the range [1, {max}]"),
+            ValidationError::Limit{value, max} => write!(f, "invalid limit \"{value:?}\". Must be in the range [1, {max}]"),
+            ValidationError::GeometryTolerance(value) => write!(f, "invalid geometry_tolerance \"{value:?}\". Must be a non-negative number of degrees"),
+            ValidationError::Tile(value) => write!(f, "invalid tile \"{value:?}\". Expected \"/tiles/{{z}}/{{x}}/{{y}}.mvt\" with z in range [0, 22] and x, y in range [0, 2^z)"),
+            ValidationError::DocumentCount{index, count, min} => write!(f, "index \"{index}\" has {count} documents, expected at least {min}"),
+            ValidationError::MaxCountDrop(value) => write!(f, "invalid max_count_drop \"{value:?}\". Must be in the range [0, 1]"),
+            ValidationError::Schema(value) => write!(f, "index does not match the expected schema: {value}"),
+            ValidationError::ConcreteIndex(value) => write!(f, "\"{value}\" is an index, not an alias. Set PHOTON_INDEX to an alias to swap indexes")
         };
     }
 }
@@ -67,6 +276,27 @@ impl From<ElasticsearchError> for PhotonError {
     }
 }
 
+impl From<AuthError> for PhotonError {
+    fn from(value: AuthError) -> Self {
+        return PhotonError::Auth(value);
+    }
+}
+
+impl From<SchemaError> for PhotonError {
+    fn from(value: SchemaError) -> Self {
+        return match value {
+            SchemaError::Elasticsearch(err) => err,
+            _ => PhotonError::Validation(ValidationError::Schema(value.to_string())),
+        };
+    }
+}
+
+impl From<LimitError> for PhotonError {
+    fn from(value: LimitError) -> Self {
+        return PhotonError::Limit(value);
+    }
+}
+
 impl From<ValidationError> for PhotonError {
     fn from(value: ValidationError) -> Self {
         return PhotonError::Validation(value);
@@ -0,0 +1,198 @@
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct EvaluationQuery {
+    pub q: String,
+    pub lang: Option<String>,
+    pub lon: Option<f32>,
+    pub lat: Option<f32>,
+    pub expected: Vec<OsmRef>,
+}
+
+// Ids are only unique per OSM type, a node and a way can share the same id
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct OsmRef {
+    pub osm_type: String,
+    pub osm_id: i64,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RankingMetrics {
+    pub precision_at_k: f64,
+    pub reciprocal_rank: f64,
+    pub ndcg_at_k: f64,
+}
+
+pub fn load_evaluation_queries(contents: &str) -> Result<Vec<EvaluationQuery>, serde_json::Error> {
+    return contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(serde_json::from_str)
+        .collect();
+}
+
+pub fn evaluate_ranking(returned: &[OsmRef], expected: &[OsmRef], k: usize) -> RankingMetrics {
+    if k == 0 || expected.is_empty() {
+        return RankingMetrics::default();
+    }
+
+    // A place returned twice is only relevant once, later copies would inflate hits and DCG
+    let mut unique: Vec<&OsmRef> = Vec::with_capacity(returned.len());
+    for id in returned {
+        if !unique.contains(&id) {
+            unique.push(id);
+        }
+    }
+    let returned = unique;
+
+    let top_k = &returned[..std::cmp::min(k, returned.len())];
+
+    let hits = top_k.iter().filter(|id| expected.contains(id)).count();
+
+    let reciprocal_rank = match returned.iter().position(|id| expected.contains(id)) {
+        Some(position) => 1.0 / (position + 1) as f64,
+        None => 0.0,
+    };
+
+    let dcg: f64 = top_k
+        .iter()
+        .enumerate()
+        .filter(|(_, id)| expected.contains(id))
+        .map(|(position, _)| 1.0 / ((position + 2) as f64).log2())
+        .sum();
+
+    let ideal_dcg: f64 = (0..std::cmp::min(k, expected.len()))
+        .map(|position| 1.0 / ((position + 2) as f64).log2())
+        .sum();
+
+    return RankingMetrics {
+        precision_at_k: hits as f64 / k as f64,
+        reciprocal_rank,
+        ndcg_at_k: dcg / ideal_dcg,
+    };
+}
+
+pub fn mean_metrics(metrics: &[RankingMetrics]) -> RankingMetrics {
+    if metrics.is_empty() {
+        return RankingMetrics::default();
+    }
+
+    let count = metrics.len() as f64;
+
+    return RankingMetrics {
+        precision_at_k: metrics.iter().map(|m| m.precision_at_k).sum::<f64>() / count,
+        reciprocal_rank: metrics.iter().map(|m| m.reciprocal_rank).sum::<f64>() / count,
+        ndcg_at_k: metrics.iter().map(|m| m.ndcg_at_k).sum::<f64>() / count,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(osm_id: i64) -> OsmRef {
+        return OsmRef {
+            osm_type: "N".to_string(),
+            osm_id,
+        };
+    }
+
+    fn way(osm_id: i64) -> OsmRef {
+        return OsmRef {
+            osm_type: "W".to_string(),
+            osm_id,
+        };
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn perfect_ranking() {
+        let metrics = evaluate_ranking(&[node(1), node(2)], &[node(1), node(2)], 2);
+
+        assert_close(metrics.precision_at_k, 1.0);
+        assert_close(metrics.reciprocal_rank, 1.0);
+        assert_close(metrics.ndcg_at_k, 1.0);
+    }
+
+    #[test]
+    fn hit_at_second_position() {
+        let metrics = evaluate_ranking(&[node(3), node(1), node(4)], &[node(1)], 3);
+
+        assert_close(metrics.precision_at_k, 1.0 / 3.0);
+        assert_close(metrics.reciprocal_rank, 0.5);
+        assert_close(metrics.ndcg_at_k, 1.0 / 3f64.log2());
+    }
+
+    #[test]
+    fn reciprocal_rank_looks_beyond_k() {
+        let metrics = evaluate_ranking(&[node(3), node(4), node(1)], &[node(1)], 2);
+
+        assert_close(metrics.precision_at_k, 0.0);
+        assert_close(metrics.reciprocal_rank, 1.0 / 3.0);
+        assert_close(metrics.ndcg_at_k, 0.0);
+    }
+
+    #[test]
+    fn same_id_with_other_type_is_no_hit() {
+        let metrics = evaluate_ranking(&[way(1)], &[node(1)], 1);
+
+        assert_close(metrics.precision_at_k, 0.0);
+        assert_close(metrics.reciprocal_rank, 0.0);
+    }
+
+    #[test]
+    fn repeated_results_count_once() {
+        let metrics = evaluate_ranking(&[node(1), node(1), node(2)], &[node(1), node(2)], 2);
+
+        assert!(metrics.ndcg_at_k <= 1.0);
+        assert!(metrics.precision_at_k <= 1.0);
+        assert_close(metrics.ndcg_at_k, 1.0);
+    }
+
+    #[test]
+    fn empty_expectations_score_zero() {
+        let metrics = evaluate_ranking(&[node(1)], &[], 10);
+
+        assert_close(metrics.precision_at_k, 0.0);
+        assert_close(metrics.ndcg_at_k, 0.0);
+    }
+
+    #[test]
+    fn mean_of_metrics() {
+        let metrics = mean_metrics(&[
+            evaluate_ranking(&[node(1)], &[node(1)], 1),
+            evaluate_ranking(&[node(2)], &[node(1)], 1),
+        ]);
+
+        assert_close(metrics.precision_at_k, 0.5);
+        assert_close(metrics.reciprocal_rank, 0.5);
+        assert_close(metrics.ndcg_at_k, 0.5);
+        assert_close(mean_metrics(&[]).precision_at_k, 0.0);
+    }
+
+    #[test]
+    fn load_queries_skips_blank_lines() {
+        let contents = r#"{"q": "berlin", "expected": [{"osm_type": "R", "osm_id": 62422}]}
+
+{"q": "hamburg", "lang": "de", "expected": []}"#;
+        let queries = load_evaluation_queries(contents).unwrap();
+
+        assert_eq!(queries.len(), 2);
+        assert_eq!(
+            queries[0].expected,
+            vec![OsmRef {
+                osm_type: "R".to_string(),
+                osm_id: 62422
+            }]
+        );
+        assert_eq!(queries[1].lang.as_deref(), Some("de"));
+    }
+}
@@ -0,0 +1,128 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::address_format::format_label;
+use crate::response::{PhotonGeometry, PhotonResponse, PhotonResponseFeature};
+
+const GEOCODEJSON_VERSION: &str = "0.1.0";
+const ATTRIBUTION: &str = "Data © OpenStreetMap contributors";
+const LICENCE: &str = "ODbL";
+
+#[derive(Debug, Serialize)]
+pub struct GeocodeJsonResponse {
+    pub r#type: String,
+    pub geocoding: GeocodeJsonHeader,
+    pub features: Vec<GeocodeJsonFeature>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GeocodeJsonHeader {
+    pub version: String,
+    pub attribution: String,
+    pub licence: String,
+    pub query: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GeocodeJsonFeature {
+    pub r#type: String,
+    pub properties: GeocodeJsonProperties,
+    pub geometry: PhotonGeometry,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GeocodeJsonProperties {
+    pub geocoding: GeocodeJsonGeocoding,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GeocodeJsonGeocoding {
+    pub place_id: i64,
+    pub osm_type: String,
+    pub osm_id: i64,
+    pub osm_key: String,
+    pub osm_value: String,
+    pub r#type: String,
+    pub label: Option<String>,
+    pub name: Option<String>,
+    pub housenumber: Option<String>,
+    pub street: Option<String>,
+    pub locality: Option<String>,
+    pub postcode: Option<String>,
+    pub district: Option<String>,
+    pub city: Option<String>,
+    pub county: Option<String>,
+    pub state: Option<String>,
+    pub country: Option<String>,
+    pub country_code: Option<String>,
+    pub admin: BTreeMap<String, String>,
+}
+
+pub fn to_geocodejson(response: PhotonResponse, query: Option<String>) -> GeocodeJsonResponse {
+    return GeocodeJsonResponse {
+        r#type: "FeatureCollection".to_string(),
+        geocoding: GeocodeJsonHeader {
+            version: GEOCODEJSON_VERSION.to_string(),
+            attribution: ATTRIBUTION.to_string(),
+            licence: LICENCE.to_string(),
+            query,
+        },
+        features: response
+            .features
+            .into_iter()
+            .map(feature_to_geocodejson)
+            .collect(),
+    };
+}
+
+fn feature_to_geocodejson(feature: PhotonResponseFeature) -> GeocodeJsonFeature {
+    let properties = feature.properties;
+
+    let mut admin = BTreeMap::new();
+    for (level, value) in [
+        ("level2", &properties.country),
+        ("level4", &properties.state),
+        ("level6", &properties.county),
+        ("level8", &properties.city),
+        ("level9", &properties.district),
+        ("level10", &properties.locality),
+    ] {
+        if let Some(value) = value {
+            admin.insert(level.to_string(), value.clone());
+        }
+    }
+
+    let r#type = match properties.r#type.as_str() {
+        "district" => "locality",
+        "county" | "state" => "region",
+        other => other,
+    };
+
+    return GeocodeJsonFeature {
+        r#type: "Feature".to_string(),
+        properties: GeocodeJsonProperties {
+            geocoding: GeocodeJsonGeocoding {
+                place_id: properties.place_id,
+                osm_type: properties.osm_type.clone(),
+                osm_id: properties.osm_id,
+                osm_key: properties.osm_key.clone(),
+                osm_value: properties.osm_value.clone(),
+                r#type: r#type.to_string(),
+                label: format_label(&properties),
+                name: properties.name,
+                housenumber: properties.housenumber,
+                street: properties.street,
+                locality: properties.locality,
+                postcode: properties.postcode,
+                district: properties.district,
+                city: properties.city,
+                county: properties.county,
+                state: properties.state,
+                country: properties.country,
+                country_code: properties.countrycode,
+                admin,
+            },
+        },
+        geometry: properties.geometry,
+    };
+}
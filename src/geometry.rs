@@ -0,0 +1,109 @@
+use crate::request::GeometryDetail;
+use crate::response::{FullGeometry, PhotonResponse, Position};
+
+pub fn apply_geometry_detail(
+    mut response: PhotonResponse,
+    detail: &GeometryDetail,
+    tolerance: &Option<f64>,
+) -> PhotonResponse {
+    for feature in &mut response.features {
+        let properties = &mut feature.properties;
+        properties.full_geometry = match (detail, properties.full_geometry.take()) {
+            (GeometryDetail::Full, Some(geometry)) => match tolerance {
+                Some(tolerance) if *tolerance > 0.0 => Some(simplify(geometry, tolerance)),
+                _ => Some(geometry),
+            },
+            _ => None,
+        };
+    }
+    return response;
+}
+
+fn simplify(geometry: FullGeometry, tolerance: &f64) -> FullGeometry {
+    return match geometry {
+        FullGeometry::LineString(line) => FullGeometry::LineString(simplify_line(&line, tolerance)),
+        FullGeometry::MultiLineString(lines) => FullGeometry::MultiLineString(
+            lines
+                .iter()
+                .map(|line| simplify_line(line, tolerance))
+                .collect(),
+        ),
+        FullGeometry::Polygon(rings) => FullGeometry::Polygon(simplify_polygon(&rings, tolerance)),
+        FullGeometry::MultiPolygon(polygons) => FullGeometry::MultiPolygon(
+            polygons
+                .iter()
+                .map(|rings| simplify_polygon(rings, tolerance))
+                .collect(),
+        ),
+        geometry => geometry,
+    };
+}
+
+fn simplify_polygon(rings: &[Vec<Position>], tolerance: &f64) -> Vec<Vec<Position>> {
+    return rings
+        .iter()
+        .map(|ring| {
+            // a closed ring needs at least 4 positions, keep the original if simplifying collapses it
+            let simplified = simplify_line(ring, tolerance);
+            if simplified.len() < 4 {
+                ring.clone()
+            } else {
+                simplified
+            }
+        })
+        .collect();
+}
+
+// Douglas-Peucker, iterative to avoid deep recursion on long lines
+fn simplify_line(line: &[Position], tolerance: &f64) -> Vec<Position> {
+    if line.len() < 3 {
+        return line.to_vec();
+    }
+
+    let mut keep = vec![false; line.len()];
+    keep[0] = true;
+    keep[line.len() - 1] = true;
+
+    let mut stack = vec![(0, line.len() - 1)];
+    while let Some((start, end)) = stack.pop() {
+        let mut max_distance = 0.0;
+        let mut max_index = start;
+        for index in start + 1..end {
+            let distance = segment_distance(&line[index], &line[start], &line[end]);
+            if distance > max_distance {
+                max_distance = distance;
+                max_index = index;
+            }
+        }
+
+        if max_distance > *tolerance {
+            keep[max_index] = true;
+            stack.push((start, max_index));
+            stack.push((max_index, end));
+        }
+    }
+
+    return line
+        .iter()
+        .zip(keep)
+        .filter(|(_, keep)| *keep)
+        .map(|(position, _)| *position)
+        .collect();
+}
+
+fn segment_distance(point: &Position, start: &Position, end: &Position) -> f64 {
+    let dx = end[0] - start[0];
+    let dy = end[1] - start[1];
+    let length_squared = dx * dx + dy * dy;
+
+    if length_squared == 0.0 {
+        return ((point[0] - start[0]).powi(2) + (point[1] - start[1]).powi(2)).sqrt();
+    }
+
+    let t = (((point[0] - start[0]) * dx + (point[1] - start[1]) * dy) / length_squared)
+        .clamp(0.0, 1.0);
+    let x = start[0] + t * dx;
+    let y = start[1] + t * dy;
+
+    return ((point[0] - x).powi(2) + (point[1] - y).powi(2)).sqrt();
+}
@@ -0,0 +1,166 @@
+use std::collections::HashSet;
+
+use crate::errors::ValidationError;
+use crate::request::{PhotonLookupRequest, PhotonReverseRequest, PhotonSearchRequest};
+use crate::response::{PhotonResponse, PhotonResponseProperties};
+
+// Messages and the service are generated from proto/photon.proto by build.rs
+tonic::include_proto!("photon");
+
+impl TryFrom<SearchRequest> for PhotonSearchRequest {
+    type Error = ValidationError;
+
+    // The bbox ranges are checked with the HTTP parameters, only the length is specific to gRPC
+    fn try_from(request: SearchRequest) -> Result<Self, Self::Error> {
+        let bbox = match request.bbox.as_slice() {
+            [] => None,
+            [min_lon, min_lat, max_lon, max_lat] => Some([*min_lon, *min_lat, *max_lon, *max_lat]),
+            values => return Err(ValidationError::BboxLength(values.len())),
+        };
+
+        return Ok(PhotonSearchRequest {
+            q: Some(request.q),
+            lang: request.lang,
+            lon: request.lon,
+            lat: request.lat,
+            limit: request.limit,
+            location_bias_scale: request.location_bias_scale,
+            radius: request.radius,
+            bbox,
+            zoom: request.zoom,
+            osm_tag: to_set(request.osm_tag),
+            layer: to_set(request.layer),
+            profile: request.profile,
+            autocomplete: request.autocomplete,
+            highlight: None,
+            format: None,
+            extra_columns: None,
+            geometry: None,
+            geometry_tolerance: None,
+            debug: None,
+        });
+    }
+}
+
+impl From<ReverseRequest> for PhotonReverseRequest {
+    fn from(request: ReverseRequest) -> Self {
+        return PhotonReverseRequest {
+            lang: request.lang,
+            lon: request.lon,
+            lat: request.lat,
+            radius: request.radius,
+            query_string_filter: request.query_string_filter,
+            distance_sort: request.distance_sort,
+            limit: request.limit,
+            osm_tag: to_set(request.osm_tag),
+            layer: to_set(request.layer),
+            format: None,
+            extra_columns: None,
+            geometry: None,
+            geometry_tolerance: None,
+            debug: None,
+        };
+    }
+}
+
+impl From<LookupRequest> for PhotonLookupRequest {
+    fn from(request: LookupRequest) -> Self {
+        return PhotonLookupRequest {
+            place_id: request.place_id,
+            lang: request.lang,
+            format: None,
+            extra_columns: None,
+            geometry: None,
+            geometry_tolerance: None,
+        };
+    }
+}
+
+impl From<PhotonResponse> for SearchResponse {
+    fn from(response: PhotonResponse) -> Self {
+        return SearchResponse {
+            places: response
+                .features
+                .into_iter()
+                .map(|feature| to_place(feature.properties))
+                .collect(),
+            lenient: response.lenient,
+        };
+    }
+}
+
+fn to_set(values: Vec<String>) -> Option<HashSet<String>> {
+    if values.is_empty() {
+        return None;
+    }
+    return Some(values.into_iter().collect());
+}
+
+fn to_place(properties: PhotonResponseProperties) -> Place {
+    let [lon, lat] = properties.geometry.coordinates;
+    let match_type = properties
+        .match_type
+        .map(|match_type| match_type.as_str().to_string());
+
+    return Place {
+        place_id: properties.place_id,
+        parent_place_id: properties.parent_place_id,
+        osm_type: properties.osm_type,
+        osm_id: properties.osm_id,
+        osm_key: properties.osm_key,
+        osm_value: properties.osm_value,
+        r#type: properties.r#type,
+        name: properties.name,
+        housenumber: properties.housenumber,
+        street: properties.street,
+        locality: properties.locality,
+        district: properties.district,
+        postcode: properties.postcode,
+        city: properties.city,
+        county: properties.county,
+        state: properties.state,
+        country: properties.country,
+        countrycode: properties.countrycode,
+        formatted: properties.formatted,
+        lon,
+        lat,
+        extent: properties
+            .extent
+            .map(|extent| extent.to_vec())
+            .unwrap_or_default(),
+        score: properties.score,
+        match_type,
+        extra: properties.extra.unwrap_or_default(),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search_request(bbox: Vec<f32>) -> SearchRequest {
+        return SearchRequest {
+            q: "berlin".to_string(),
+            bbox,
+            ..Default::default()
+        };
+    }
+
+    #[test]
+    fn converts_bbox() {
+        let request = PhotonSearchRequest::try_from(search_request(vec![])).unwrap();
+        assert_eq!(request.bbox, None);
+
+        let request =
+            PhotonSearchRequest::try_from(search_request(vec![13.0, 52.0, 14.0, 53.0])).unwrap();
+        assert_eq!(request.bbox, Some([13.0, 52.0, 14.0, 53.0]));
+    }
+
+    #[test]
+    fn rejects_partial_bbox() {
+        assert!(matches!(
+            PhotonSearchRequest::try_from(search_request(vec![13.0, 52.0, 14.0])),
+            Err(ValidationError::BboxLength(3))
+        ));
+    }
+}
@@ -0,0 +1,25 @@
+pub mod address_format;
+pub mod address_type;
+pub mod auth;
+pub mod config;
+pub mod country_codes;
+pub mod doc;
+pub mod elastic;
+pub mod errors;
+pub mod evaluation;
+pub mod geocodejson;
+pub mod geometry;
+pub mod grpc;
+pub mod limits;
+pub mod metrics;
+pub mod nominatim;
+pub mod output;
+pub mod pelias;
+pub mod query;
+pub mod request;
+pub mod response;
+pub mod schema;
+pub mod tile;
+pub mod tls;
+pub mod validation;
+pub mod vector_tile;
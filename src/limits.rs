@@ -0,0 +1,328 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::auth::{Authenticator, API_KEY_HEADER, API_KEY_PARAM};
+use crate::config::{ConcurrencyLimitConfig, RateLimitConfig};
+use crate::errors::LimitError;
+use crate::metrics::Metrics;
+
+const LOAD_SHED_METRIC: &str = "photon_load_shed_total";
+const UNLIMITED_PATHS: [&str; 2] = ["/health", "/metrics"];
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+struct Buckets {
+    clients: HashMap<String, Bucket>,
+    swept: Instant,
+}
+
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    authenticator: Arc<Authenticator>,
+    buckets: Mutex<Buckets>,
+    metrics: Arc<Metrics>,
+}
+
+impl RateLimiter {
+    pub fn new(
+        config: RateLimitConfig,
+        authenticator: Arc<Authenticator>,
+        metrics: Arc<Metrics>,
+    ) -> Self {
+        return RateLimiter {
+            config,
+            authenticator,
+            buckets: Mutex::new(Buckets {
+                clients: HashMap::new(),
+                swept: Instant::now(),
+            }),
+            metrics,
+        };
+    }
+
+    pub fn acquire(&self, client: &str) -> Result<(), LimitError> {
+        return self.acquire_at(client, Instant::now());
+    }
+
+    fn acquire_at(&self, client: &str, now: Instant) -> Result<(), LimitError> {
+        let mut buckets = self.buckets.lock().unwrap();
+
+        // Buckets idle long enough to refill completely behave exactly like new ones, dropping
+        // them once per refill period keeps the map bounded by the clients seen in that period
+        let idle = self.refill_period();
+        if now.duration_since(buckets.swept) >= idle {
+            buckets
+                .clients
+                .retain(|_, bucket| now.duration_since(bucket.updated) < idle);
+            buckets.swept = now;
+        }
+
+        let bucket = buckets.clients.entry(client.to_string()).or_insert(Bucket {
+            tokens: self.config.burst,
+            updated: now,
+        });
+        bucket.tokens = self.refill(bucket, now);
+        bucket.updated = now;
+
+        if bucket.tokens < 1.0 {
+            let retry_after = ((1.0 - bucket.tokens) / self.config.per_second).ceil() as u64;
+            self.metrics
+                .increment(LOAD_SHED_METRIC, &[("reason", "rate_limited")]);
+            return Err(LimitError::RateLimited {
+                retry_after: retry_after.max(1),
+            });
+        }
+
+        bucket.tokens -= 1.0;
+        return Ok(());
+    }
+
+    fn refill(&self, bucket: &Bucket, now: Instant) -> f64 {
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        return (bucket.tokens + elapsed * self.config.per_second).min(self.config.burst);
+    }
+
+    fn refill_period(&self) -> Duration {
+        return Duration::from_secs_f64(self.config.burst / self.config.per_second)
+            .max(Duration::from_secs(1));
+    }
+}
+
+pub struct ConcurrencyLimiter {
+    config: ConcurrencyLimitConfig,
+    semaphore: Arc<Semaphore>,
+    queued: AtomicUsize,
+    metrics: Arc<Metrics>,
+}
+
+impl ConcurrencyLimiter {
+    pub fn new(config: ConcurrencyLimitConfig, metrics: Arc<Metrics>) -> Self {
+        return ConcurrencyLimiter {
+            config,
+            semaphore: Arc::new(Semaphore::new(config.max_in_flight)),
+            queued: AtomicUsize::new(0),
+            metrics,
+        };
+    }
+
+    pub async fn acquire(&self) -> Result<OwnedSemaphorePermit, LimitError> {
+        if let Ok(permit) = self.semaphore.clone().try_acquire_owned() {
+            return Ok(permit);
+        }
+
+        let retry_after = self.config.queue_timeout_ms.div_ceil(1000).max(1);
+
+        if self.queued.fetch_add(1, Ordering::SeqCst) >= self.config.max_queued {
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+            self.metrics
+                .increment(LOAD_SHED_METRIC, &[("reason", "queue_full")]);
+            return Err(LimitError::QueueFull { retry_after });
+        }
+
+        let permit = tokio::time::timeout(
+            Duration::from_millis(self.config.queue_timeout_ms),
+            self.semaphore.clone().acquire_owned(),
+        )
+        .await;
+        self.queued.fetch_sub(1, Ordering::SeqCst);
+
+        return match permit {
+            Ok(Ok(permit)) => Ok(permit),
+            _ => {
+                self.metrics
+                    .increment(LOAD_SHED_METRIC, &[("reason", "queue_timeout")]);
+                Err(LimitError::QueueTimeout { retry_after })
+            }
+        };
+    }
+}
+
+pub async fn rate_limit(
+    State(limiter): State<Arc<RateLimiter>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, LimitError> {
+    if UNLIMITED_PATHS.contains(&request.uri().path()) {
+        return Ok(next.run(request).await);
+    }
+
+    limiter.acquire(&request_client_identity(&request, &limiter.authenticator))?;
+
+    return Ok(next.run(request).await);
+}
+
+// Answers 504 when the route does not respond in time, dropping the pending Elasticsearch requests
+pub async fn request_timeout(
+    State(timeout): State<Duration>,
+    request: Request,
+    next: Next,
+) -> Result<Response, LimitError> {
+    return match tokio::time::timeout(timeout, next.run(request)).await {
+        Ok(response) => Ok(response),
+        Err(_) => Err(LimitError::Timeout),
+    };
+}
+
+// Requests with a valid API key share one bucket per key, everything else is limited per IP so
+// made up keys can not be used to get fresh buckets
+pub fn client_identity(
+    key: Option<&str>,
+    address: Option<SocketAddr>,
+    authenticator: &Authenticator,
+) -> String {
+    if let Some(key) = key {
+        if authenticator.is_valid(key) {
+            return format!("key:{}", key);
+        }
+    }
+
+    return match address {
+        Some(address) => format!("ip:{}", address.ip()),
+        None => "ip:unknown".to_string(),
+    };
+}
+
+fn request_client_identity(request: &Request, authenticator: &Authenticator) -> String {
+    let header = request
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(|key| key.to_string());
+    let param = || {
+        let params: Vec<(String, String)> = match axum::extract::Query::try_from_uri(request.uri())
+        {
+            Ok(axum::extract::Query(params)) => params,
+            Err(_) => vec![],
+        };
+        return params
+            .into_iter()
+            .find(|(name, _)| name == API_KEY_PARAM)
+            .map(|(_, value)| value);
+    };
+    let address = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(address)| *address);
+
+    return client_identity(header.or_else(param).as_deref(), address, authenticator);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ApiKeyConfig;
+
+    fn limiter(per_second: f64, burst: f64) -> RateLimiter {
+        let metrics = Arc::new(Metrics::default());
+        return RateLimiter::new(
+            RateLimitConfig { per_second, burst },
+            Arc::new(Authenticator::new(HashMap::new(), metrics.clone())),
+            metrics,
+        );
+    }
+
+    #[test]
+    fn allows_burst_then_limits() {
+        let limiter = limiter(1.0, 3.0);
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert!(limiter.acquire_at("ip:1", now).is_ok());
+        }
+        assert!(matches!(
+            limiter.acquire_at("ip:1", now),
+            Err(LimitError::RateLimited { retry_after: 1 })
+        ));
+    }
+
+    #[test]
+    fn refills_over_time() {
+        let limiter = limiter(2.0, 2.0);
+        let now = Instant::now();
+
+        assert!(limiter.acquire_at("ip:1", now).is_ok());
+        assert!(limiter.acquire_at("ip:1", now).is_ok());
+        assert!(limiter.acquire_at("ip:1", now).is_err());
+        assert!(limiter
+            .acquire_at("ip:1", now + Duration::from_millis(500))
+            .is_ok());
+        assert!(limiter
+            .acquire_at("ip:1", now + Duration::from_millis(500))
+            .is_err());
+    }
+
+    #[test]
+    fn retry_after_rounds_up() {
+        let limiter = limiter(0.25, 1.0);
+        let now = Instant::now();
+
+        assert!(limiter.acquire_at("ip:1", now).is_ok());
+        assert!(matches!(
+            limiter.acquire_at("ip:1", now),
+            Err(LimitError::RateLimited { retry_after: 4 })
+        ));
+    }
+
+    #[test]
+    fn clients_have_separate_buckets() {
+        let limiter = limiter(1.0, 1.0);
+        let now = Instant::now();
+
+        assert!(limiter.acquire_at("ip:1", now).is_ok());
+        assert!(limiter.acquire_at("ip:1", now).is_err());
+        assert!(limiter.acquire_at("ip:2", now).is_ok());
+    }
+
+    #[test]
+    fn sweeps_idle_buckets() {
+        let limiter = limiter(1.0, 2.0);
+        let now = Instant::now();
+
+        assert!(limiter.acquire_at("ip:1", now).is_ok());
+        assert!(limiter
+            .acquire_at("ip:2", now + Duration::from_secs(1))
+            .is_ok());
+        assert!(limiter
+            .acquire_at("ip:2", now + Duration::from_secs(2))
+            .is_ok());
+
+        let buckets = limiter.buckets.lock().unwrap();
+        assert_eq!(buckets.clients.len(), 1);
+        assert!(buckets.clients.contains_key("ip:2"));
+    }
+
+    #[test]
+    fn identifies_clients_by_valid_key_or_address() {
+        let metrics = Arc::new(Metrics::default());
+        let key = ApiKeyConfig {
+            key: "secret".to_string(),
+            rate_limit: None,
+            endpoints: None,
+            max_limit: None,
+            languages: None,
+        };
+        let authenticator = Authenticator::new(HashMap::from([("app".to_string(), key)]), metrics);
+        let address: SocketAddr = "10.0.0.1:4000".parse().unwrap();
+
+        assert_eq!(
+            client_identity(Some("secret"), Some(address), &authenticator),
+            "key:secret"
+        );
+        assert_eq!(
+            client_identity(Some("made-up"), Some(address), &authenticator),
+            "ip:10.0.0.1"
+        );
+        assert_eq!(client_identity(None, None, &authenticator), "ip:unknown");
+    }
+}
@@ -1,90 +1,661 @@
-mod address_type;
-mod config;
-mod doc;
-mod elastic;
-mod errors;
-mod query;
-mod request;
-mod response;
-mod validation;
-
-use crate::config::{load_api_config, load_language_config};
-use crate::errors::PhotonError;
-use crate::request::{PhotonLookupRequest, PhotonReverseRequest, PhotonSearchRequest};
-use crate::validation::{
-    validate_bbox, validate_lang_parameter, validate_location_bias,
-    validate_reverse_request_parameters, validate_search_request_parameters,
-};
-use axum::extract::State;
-use axum::{routing::get, Router};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{ConnectInfo, MatchedPath, Path, Request, State};
+use axum::http::header::{ACCEPT, ACCEPT_LANGUAGE, CONTENT_TYPE, RETRY_AFTER};
+use axum::http::{HeaderMap, HeaderName, HeaderValue, Method};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Extension, Router};
 use axum_extra::extract::Query;
 use axum_macros::debug_handler;
-use elasticsearch::http::headers::{HeaderValue, AUTHORIZATION};
-use elasticsearch::http::transport::{CloudConnectionPool, TransportBuilder};
 use elasticsearch::Elasticsearch;
-use query::build_search_query;
+use elasticsearch_dsl::Search;
+use photon_api::auth::{
+    constant_time_eq, AuthRequest, Authenticator, MaxLimit, API_KEY_HEADER, API_KEY_PARAM,
+};
+use photon_api::config::{
+    load_api_config, load_api_key_config, load_language_config, load_ranking_config,
+    LenientSearchMode,
+};
+use photon_api::doc::PhotonDocument;
+use photon_api::errors::{AuthError, LimitError, PhotonError, ValidationError};
+use photon_api::geometry::apply_geometry_detail;
+use photon_api::grpc::photon_server::{Photon, PhotonServer};
+use photon_api::grpc::{
+    BatchSearchRequest, BatchSearchResult, LookupRequest, ReverseRequest, SearchRequest,
+    SearchResponse,
+};
+use photon_api::limits::{
+    client_identity, rate_limit, request_timeout, ConcurrencyLimiter, RateLimiter,
+};
+use photon_api::metrics::{Metrics, METRICS_CONTENT_TYPE};
+use photon_api::request::{
+    GeometryDetail, OutputFormat, PhotonAliasSwapRequest, PhotonLookupRequest,
+    PhotonReverseRequest, PhotonSearchRequest, PhotonSessionMessage, PhotonSessionRequest,
+    PhotonSimilarRequest, PhotonTileRequest,
+};
+use photon_api::schema::check_index_schema;
+use photon_api::tls::{serve_tls, tls_acceptor, watch_certificates, CertificateResolver};
+use photon_api::validation::{
+    validate_bbox, validate_geometry_tolerance, validate_lang_parameter, validate_lat,
+    validate_location_bias, validate_lon, validate_max_count_drop, validate_profile_parameter,
+    validate_reverse_request_parameters, validate_search_request_parameters,
+    validate_similar_request_parameters,
+};
+use serde_json::json;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Semaphore};
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tower_http::compression::CompressionLayer;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+
+use photon_api::elastic::{
+    count_documents, create_elasticsearch_client, fetch_alias_indexes, fetch_document,
+    index_exists, send_lookup, send_photon_multi_query, send_photon_query, send_photon_suggest,
+    swap_alias,
+};
+use photon_api::nominatim::{
+    layer_for_zoom, parse_country_codes, parse_viewbox, render_reverse, render_search,
+    resolve_language, NominatimFormat, NominatimGeoJsonFeature, NominatimGeoJsonProperties,
+    NominatimGeoJsonResponse, NominatimPlace, NominatimReverseRequest, NominatimSearchRequest,
+};
+use photon_api::output::{render_response, resolve_output_format};
+use photon_api::pelias::{
+    parse_boundary_country, parse_boundary_rect, parse_layers, to_pelias, validate_text,
+    PeliasFeature, PeliasHeader, PeliasProperties, PeliasResponse, PeliasReverseRequest,
+    PeliasSearchRequest,
+};
+use photon_api::query::{
+    add_geometry_source, add_highlight, build_autocomplete_query, build_autocomplete_suggest_query,
+    build_browse_query, build_reverse_query, build_search_query, build_similar_query, Point,
+    QueryFilters, RankingProfile,
+};
+use photon_api::response::{
+    FullGeometry, MatchType, PhotonGeometry, PhotonResponse, PhotonResponseDebug,
+    PhotonResponseFeature, PhotonResponseProperties, PhotonSessionResponse, SkippedDocument,
+};
+use photon_api::tile::{
+    layers_for_tile_zoom, parse_tile_coordinate, render_tile, tile_envelope, TILE_FEATURE_LIMIT,
+};
 
-use crate::elastic::{send_lookup, send_photon_query};
-use crate::query::build_reverse_query;
-use crate::response::PhotonResponse;
+const DEFAULT: &str = "default";
+const PUBLIC_ENDPOINTS: [&str; 2] = ["/health", "/metrics"];
+const AUTOCOMPLETE_MAX_LIMIT: i64 = 10;
+const ADMIN_ALIAS_ENDPOINT: &str = "/admin/alias";
+const SESSION_ENDPOINT: &str = "/ws/autocomplete";
+const ACCEPT_LANGUAGE_ENDPOINTS: [&str; 2] = ["/nominatim/search", "/nominatim/reverse"];
+const DEFAULT_MAX_COUNT_DROP: f64 = 0.1;
+const BATCH_SEARCH_MAX_SIZE: usize = 1000;
+const BATCH_SEARCH_CONCURRENCY: usize = 8;
+const SESSION_DEFAULT_DEBOUNCE: u64 = 100;
+const SESSION_MAX_DEBOUNCE: u64 = 1000;
 
-const DEFAULT: &'static str = "default";
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Photon API"),
+    paths(
+        health,
+        metrics,
+        search,
+        autocomplete_session,
+        reverse,
+        lookup,
+        similar,
+        tile,
+        nominatim_search,
+        nominatim_reverse,
+        pelias_search,
+        pelias_autocomplete,
+        pelias_reverse,
+        admin_alias,
+    ),
+    components(schemas(
+        PhotonResponse,
+        PhotonResponseFeature,
+        PhotonResponseProperties,
+        PhotonGeometry,
+        FullGeometry,
+        MatchType,
+        OutputFormat,
+        GeometryDetail,
+        PhotonResponseDebug,
+        SkippedDocument,
+        NominatimFormat,
+        NominatimPlace,
+        NominatimGeoJsonResponse,
+        NominatimGeoJsonFeature,
+        NominatimGeoJsonProperties,
+        PeliasResponse,
+        PeliasHeader,
+        PeliasFeature,
+        PeliasProperties,
+    ))
+)]
+struct ApiDoc;
 
 #[derive(Clone)]
 struct AppState {
     client: Elasticsearch,
+    index: String,
+    admin_api_key: Option<String>,
+    alias_swap: Arc<tokio::sync::Mutex<()>>,
+    supported_database_versions: Option<Vec<String>>,
     languages: Vec<String>,
+    profiles: HashMap<String, RankingProfile>,
+    autocomplete_suggest_field: Option<String>,
+    lenient_search_mode: LenientSearchMode,
+    authenticator: Arc<Authenticator>,
+    concurrency_limiter: Option<Arc<ConcurrencyLimiter>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    max_limit: MaxLimit,
+    metrics: Arc<Metrics>,
+}
+
+impl AppState {
+    // The state for one request, with the result count capped by the API key's max_limit
+    fn with_max_limit(&self, max_limit: MaxLimit) -> AppState {
+        let mut app_state = self.clone();
+        app_state.max_limit = max_limit;
+        return app_state;
+    }
+
+    // Explicit limits above max_limit are rejected when authorizing, this also caps the default
+    // sizes and the oversizing the handlers add on their own
+    fn clamp_size(&self, size: i64) -> i64 {
+        return match self.max_limit {
+            MaxLimit(Some(max)) => size.min(max),
+            MaxLimit(None) => size,
+        };
+    }
+
+    // The HTTP middleware charges each request, WebSocket messages and gRPC queries are charged
+    // here against the same buckets
+    fn acquire_rate_limit(&self, client: &str) -> Result<(), LimitError> {
+        return match &self.rate_limiter {
+            Some(limiter) => limiter.acquire(client),
+            None => Ok(()),
+        };
+    }
+
+    async fn search(
+        &self,
+        query: Search,
+        size: i64,
+        language: &str,
+    ) -> Result<PhotonResponse, PhotonError> {
+        return send_photon_query(
+            &self.client,
+            &self.index,
+            &self.metrics,
+            self.concurrency_limiter.as_deref(),
+            query,
+            self.clamp_size(size),
+            language,
+        )
+        .await;
+    }
+
+    async fn multi_search(
+        &self,
+        queries: Vec<Search>,
+        size: i64,
+        language: &str,
+    ) -> Result<Vec<Option<PhotonResponse>>, PhotonError> {
+        return send_photon_multi_query(
+            &self.client,
+            &self.index,
+            &self.metrics,
+            self.concurrency_limiter.as_deref(),
+            queries,
+            self.clamp_size(size),
+            language,
+        )
+        .await;
+    }
+
+    async fn suggest(&self, query: Search, language: &str) -> Result<PhotonResponse, PhotonError> {
+        return send_photon_suggest(
+            &self.client,
+            &self.index,
+            &self.metrics,
+            self.concurrency_limiter.as_deref(),
+            query,
+            language,
+        )
+        .await;
+    }
+
+    async fn lookup(
+        &self,
+        place_id: &str,
+        language: &str,
+        full_geometry: &bool,
+    ) -> Result<PhotonResponse, PhotonError> {
+        return send_lookup(
+            &self.client,
+            &self.index,
+            &self.metrics,
+            self.concurrency_limiter.as_deref(),
+            place_id,
+            language,
+            full_geometry,
+        )
+        .await;
+    }
+
+    async fn fetch_document(
+        &self,
+        place_id: &str,
+        full_geometry: &bool,
+    ) -> Result<Option<PhotonDocument>, PhotonError> {
+        return fetch_document(
+            &self.client,
+            &self.index,
+            &self.metrics,
+            self.concurrency_limiter.as_deref(),
+            place_id,
+            full_geometry,
+        )
+        .await;
+    }
 }
 
 #[tokio::main]
 async fn main() {
     let config = load_api_config();
     let languages = load_language_config();
+    let profiles = load_ranking_config();
+    let api_keys = load_api_key_config();
 
     let client =
         create_elasticsearch_client(&config.elastic_cloud_id, &config.elastic_api_key).unwrap();
 
-    let health_res = client.cat().health().send().await.unwrap().text().await.unwrap();
+    let health_res = client
+        .cat()
+        .health()
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
     println!("{}", health_res);
 
-    let app_state = AppState { client, languages };
+    if config.supported_database_versions.is_none() {
+        println!(
+            "WARNING: SUPPORTED_DATABASE_VERSIONS is not set, the database version of index \"{}\" is not checked",
+            config.index
+        );
+    }
+
+    match check_index_schema(
+        &client,
+        &config.index,
+        &languages,
+        &config.supported_database_versions,
+    )
+    .await
+    {
+        Ok(Some(version)) => println!(
+            "Index \"{}\" has database version {}",
+            config.index, version
+        ),
+        Ok(None) => println!("Index \"{}\" has no database version", config.index),
+        Err(err) => panic!(
+            "Index \"{}\" does not match the expected schema: {}",
+            config.index, err
+        ),
+    }
+
+    let shared_metrics = Arc::new(Metrics::default());
+    let authenticator = Arc::new(Authenticator::new(api_keys, shared_metrics.clone()));
+    let rate_limiter = config.rate_limit.map(|rate_limit_config| {
+        Arc::new(RateLimiter::new(
+            rate_limit_config,
+            authenticator.clone(),
+            shared_metrics.clone(),
+        ))
+    });
+
+    let app_state = AppState {
+        client,
+        index: config.index.clone(),
+        admin_api_key: config.admin_api_key.clone(),
+        alias_swap: Arc::new(tokio::sync::Mutex::new(())),
+        supported_database_versions: config.supported_database_versions.clone(),
+        languages,
+        profiles,
+        autocomplete_suggest_field: config.autocomplete_suggest_field.clone(),
+        lenient_search_mode: config.lenient_search_mode,
+        authenticator: authenticator.clone(),
+        concurrency_limiter: config
+            .concurrency_limit
+            .map(|limit| Arc::new(ConcurrencyLimiter::new(limit, shared_metrics.clone()))),
+        rate_limiter: rate_limiter.clone(),
+        max_limit: MaxLimit(None),
+        metrics: shared_metrics.clone(),
+    };
+
+    if let Some(grpc_port) = &config.grpc_port {
+        let address: SocketAddr = format!("{}:{}", config.host_address, grpc_port)
+            .parse()
+            .unwrap();
+        let service = PhotonServer::new(PhotonGrpcService {
+            app_state: app_state.clone(),
+        });
+
+        // Bind before spawning, so a taken port fails startup instead of only the gRPC task
+        let listener = match tokio::net::TcpListener::bind(address).await {
+            Ok(listener) => listener,
+            Err(err) => panic!("Could not bind gRPC address {}: {}", address, err),
+        };
+        println!("Ready to receive gRPC requests on {}", address);
+
+        tokio::spawn(async move {
+            let result = tonic::transport::Server::builder()
+                .add_service(service)
+                .serve_with_incoming(TcpListenerStream::new(listener))
+                .await;
+            if let Err(err) = result {
+                println!("gRPC server failed: {}", err);
+                std::process::exit(1);
+            }
+        });
+    }
 
-    let router = Router::new()
+    let timeout = |path: &str| {
+        middleware::from_fn_with_state(config.http.route_timeout(path), request_timeout)
+    };
+
+    let mut router = Router::new()
         .route("/health", get(health))
-        .route("/search", get(search))
-        .route("/lookup", get(lookup))
-        .route("/reverse", get(reverse))
+        .route("/metrics", get(metrics))
+        .route("/search", get(search).layer(timeout("/search")))
+        .route(SESSION_ENDPOINT, get(autocomplete_session))
+        .route("/lookup", get(lookup).layer(timeout("/lookup")))
+        .route("/reverse", get(reverse).layer(timeout("/reverse")))
+        .route("/similar", get(similar).layer(timeout("/similar")))
+        .route(
+            "/tiles/:z/:x/:y",
+            get(tile).layer(timeout("/tiles/:z/:x/:y")),
+        )
+        .nest(
+            "/v1",
+            Router::new()
+                .route("/search", get(pelias_search).layer(timeout("/v1/search")))
+                .route(
+                    "/autocomplete",
+                    get(pelias_autocomplete).layer(timeout("/v1/autocomplete")),
+                )
+                .route(
+                    "/reverse",
+                    get(pelias_reverse).layer(timeout("/v1/reverse")),
+                ),
+        )
+        .nest(
+            "/nominatim",
+            Router::new()
+                .route(
+                    "/search",
+                    get(nominatim_search).layer(timeout("/nominatim/search")),
+                )
+                .route(
+                    "/reverse",
+                    get(nominatim_reverse).layer(timeout("/nominatim/reverse")),
+                ),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            authenticate,
+        ))
+        // Added after the API key layer, admin routes check ADMIN_API_KEY themselves
+        .route(ADMIN_ALIAS_ENDPOINT, post(admin_alias))
+        // Swagger UI assets are embedded in the binary, the docs work without internet access
+        .merge(SwaggerUi::new("/docs").url("/openapi.json", ApiDoc::openapi()))
         .with_state(app_state);
 
+    // Layers added last run first: CORS, compression, then rate limiting
+    if let Some(limiter) = rate_limiter {
+        router = router.layer(middleware::from_fn_with_state(limiter, rate_limit));
+    }
+    if config.http.compression {
+        router = router.layer(CompressionLayer::new());
+    }
+    if let Some(origins) = &config.http.cors_allowed_origins {
+        router = router.layer(cors_layer(origins));
+    }
+
     let listener =
         tokio::net::TcpListener::bind(format!("{}:{}", config.host_address, config.host_port))
             .await
             .unwrap();
 
+    if let Some(tls_config) = &config.tls {
+        let resolver = Arc::new(CertificateResolver::new(tls_config).unwrap());
+        watch_certificates(
+            resolver.clone(),
+            Duration::from_secs(tls_config.reload_interval_secs),
+        );
+
+        println!("Ready to receive HTTPS requests!");
+
+        serve_tls(listener, tls_acceptor(resolver), router).await;
+        return;
+    }
+
     println!("Ready to receive requests!");
 
-    axum::serve(listener, router).await.unwrap();
+    axum::serve(
+        listener,
+        router.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
+}
+
+#[derive(Clone)]
+struct PhotonGrpcService {
+    app_state: AppState,
 }
 
-fn create_elasticsearch_client(
-    cloud_id: &str,
-    api_key: &str,
-) -> Result<Elasticsearch, PhotonError> {
-    let mut api_key_header: HeaderValue =
-        HeaderValue::from_str(&format!("ApiKey {}", api_key)).unwrap();
-    api_key_header.set_sensitive(true);
+impl PhotonGrpcService {
+    fn authorize<T>(
+        &self,
+        request: &tonic::Request<T>,
+        endpoint: &str,
+        cost: u32,
+        limits: Vec<i64>,
+        languages: Vec<&str>,
+    ) -> Result<(), AuthError> {
+        return self.app_state.authenticator.authorize(&AuthRequest {
+            key: grpc_api_key(request),
+            endpoint,
+            cost,
+            limits,
+            languages,
+        });
+    }
 
-    // NB: we may want to implement our own connection pooling, as both
-    // SingleNodeConnectionPool and CloudConnectionPool use a single connection
-    let conn_pool = CloudConnectionPool::new(cloud_id)?;
-    let transport = TransportBuilder::new(conn_pool)
-        .header(AUTHORIZATION, api_key_header)
-        .build()
-        .unwrap();
+    fn app_state<T>(&self, request: &tonic::Request<T>) -> AppState {
+        let max_limit = self
+            .app_state
+            .authenticator
+            .max_limit(grpc_api_key(request));
+        return self.app_state.with_max_limit(max_limit);
+    }
+
+    fn client_identity<T>(&self, request: &tonic::Request<T>) -> String {
+        return client_identity(
+            grpc_api_key(request),
+            request.remote_addr(),
+            &self.app_state.authenticator,
+        );
+    }
+}
+
+fn grpc_api_key<T>(request: &tonic::Request<T>) -> Option<&str> {
+    return request
+        .metadata()
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok());
+}
+
+#[tonic::async_trait]
+impl Photon for PhotonGrpcService {
+    async fn search(
+        &self,
+        request: tonic::Request<SearchRequest>,
+    ) -> Result<tonic::Response<SearchResponse>, tonic::Status> {
+        let message = request.get_ref();
+        self.authorize(
+            &request,
+            "/photon.Photon/Search",
+            1,
+            message.limit.into_iter().collect(),
+            message.lang.as_deref().into_iter().collect(),
+        )?;
+        self.app_state
+            .acquire_rate_limit(&self.client_identity(&request))?;
+
+        let app_state = self.app_state(&request);
+        let params =
+            PhotonSearchRequest::try_from(request.into_inner()).map_err(PhotonError::from)?;
+        let (result, _) = run_search(&app_state, params).await?;
+        return Ok(tonic::Response::new(result.into()));
+    }
+
+    async fn reverse(
+        &self,
+        request: tonic::Request<ReverseRequest>,
+    ) -> Result<tonic::Response<SearchResponse>, tonic::Status> {
+        let message = request.get_ref();
+        self.authorize(
+            &request,
+            "/photon.Photon/Reverse",
+            1,
+            message.limit.into_iter().collect(),
+            message.lang.as_deref().into_iter().collect(),
+        )?;
+
+        let app_state = self.app_state(&request);
+        let result = run_reverse(&app_state, request.into_inner().into()).await?;
+        return Ok(tonic::Response::new(result.into()));
+    }
+
+    async fn lookup(
+        &self,
+        request: tonic::Request<LookupRequest>,
+    ) -> Result<tonic::Response<SearchResponse>, tonic::Status> {
+        let message = request.get_ref();
+        self.authorize(
+            &request,
+            "/photon.Photon/Lookup",
+            1,
+            vec![],
+            message.lang.as_deref().into_iter().collect(),
+        )?;
+
+        let result = run_lookup(&self.app_state, request.into_inner().into()).await?;
+        return Ok(tonic::Response::new(result.into()));
+    }
+
+    type BatchSearchStream = ReceiverStream<Result<BatchSearchResult, tonic::Status>>;
+
+    async fn batch_search(
+        &self,
+        request: tonic::Request<BatchSearchRequest>,
+    ) -> Result<tonic::Response<Self::BatchSearchStream>, tonic::Status> {
+        let requests = &request.get_ref().requests;
+        if requests.len() > BATCH_SEARCH_MAX_SIZE {
+            return Err(tonic::Status::invalid_argument(format!(
+                "batch contains {} requests, at most {} are allowed",
+                requests.len(),
+                BATCH_SEARCH_MAX_SIZE
+            )));
+        }
+        self.authorize(
+            &request,
+            "/photon.Photon/BatchSearch",
+            requests.len() as u32,
+            requests
+                .iter()
+                .filter_map(|request| request.limit)
+                .collect(),
+            requests
+                .iter()
+                .filter_map(|request| request.lang.as_deref())
+                .collect(),
+        )?;
+
+        let client = self.client_identity(&request);
+        let app_state = self.app_state(&request);
+        let requests = request.into_inner().requests;
+
+        let (sender, receiver) = mpsc::channel(BATCH_SEARCH_CONCURRENCY);
+        let semaphore = Arc::new(Semaphore::new(BATCH_SEARCH_CONCURRENCY));
+
+        // results are streamed in completion order, index refers back to the request.
+        // Once the client is gone the receiver is dropped, which stops the remaining searches
+        tokio::spawn(async move {
+            for (index, request) in requests.into_iter().enumerate() {
+                let permit = tokio::select! {
+                    permit = semaphore.clone().acquire_owned() => permit.unwrap(),
+                    _ = sender.closed() => break,
+                };
+                let sender = sender.clone();
+                let app_state = app_state.clone();
+                // Each query of the batch is charged, one over the limit or invalid fails on its own
+                let params = app_state
+                    .acquire_rate_limit(&client)
+                    .map_err(PhotonError::from)
+                    .and_then(|_| {
+                        return PhotonSearchRequest::try_from(request).map_err(PhotonError::from);
+                    });
+
+                tokio::spawn(async move {
+                    let result = match params {
+                        Ok(params) => tokio::select! {
+                            result = run_search(&app_state, params) => result,
+                            _ = sender.closed() => return,
+                        },
+                        Err(err) => Err(err),
+                    };
+                    let result = match result {
+                        Ok((response, _)) => BatchSearchResult {
+                            index: index as u32,
+                            response: Some(response.into()),
+                            error: None,
+                        },
+                        Err(err) => BatchSearchResult {
+                            index: index as u32,
+                            response: None,
+                            error: Some(tonic::Status::from(err).message().to_string()),
+                        },
+                    };
+                    let _ = sender.send(Ok(result)).await;
+                    drop(permit);
+                });
+            }
+        });
 
-    return Ok(Elasticsearch::new(transport));
+        return Ok(tonic::Response::new(ReceiverStream::new(receiver)));
+    }
 }
 
+#[utoipa::path(
+    get,
+    path = "/health",
+    responses(
+        (status = 200, description = "Elasticsearch cluster health", body = String, content_type = "text/plain"),
+        (status = 500, description = "Elasticsearch error", body = String, content_type = "text/plain"),
+    )
+)]
 #[debug_handler]
 async fn health(State(app_state): State<AppState>) -> Result<String, PhotonError> {
     let response = app_state.client.cat().health().send().await?.text().await?;
@@ -92,13 +663,217 @@ async fn health(State(app_state): State<AppState>) -> Result<String, PhotonError
     Ok(response)
 }
 
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    responses(
+        (status = 200, description = "Request, API key and load shedding counters in the Prometheus text format", body = String, content_type = "text/plain"),
+    )
+)]
+async fn metrics(State(app_state): State<AppState>) -> Response {
+    return (
+        [(CONTENT_TYPE, METRICS_CONTENT_TYPE)],
+        app_state.metrics.render(),
+    )
+        .into_response();
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/alias",
+    params(PhotonAliasSwapRequest),
+    responses(
+        (status = 200, description = "Alias now points to the new index", body = Object),
+        (status = 400, description = "Invalid parameter, too few documents, the index does not match the expected schema or PHOTON_INDEX is not an alias", body = String, content_type = "text/plain"),
+        (status = 401, description = "Missing or invalid admin API key", body = String, content_type = "text/plain"),
+        (status = 403, description = "Admin endpoints are disabled", body = String, content_type = "text/plain"),
+        (status = 429, description = "Rate limit exceeded, retry after the seconds in the Retry-After header", body = String, content_type = "text/plain"),
+    )
+)]
+#[debug_handler]
+async fn admin_alias(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<PhotonAliasSwapRequest>,
+) -> Result<axum::Json<serde_json::Value>, PhotonError> {
+    let admin_api_key = match &app_state.admin_api_key {
+        Some(admin_api_key) => admin_api_key,
+        None => return Err(AuthError::Endpoint(ADMIN_ALIAS_ENDPOINT.into()).into()),
+    };
+    match headers
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+    {
+        Some(key) if constant_time_eq(key.as_bytes(), admin_api_key.as_bytes()) => {}
+        Some(_) => return Err(AuthError::InvalidKey.into()),
+        None => return Err(AuthError::MissingKey.into()),
+    }
+
+    validate_max_count_drop(&params.max_count_drop)?;
+    let max_count_drop = params.max_count_drop.unwrap_or(DEFAULT_MAX_COUNT_DROP);
+
+    // Swaps read the current alias before changing it, running two at once could lose one
+    let _swap = app_state.alias_swap.lock().await;
+
+    let alias = &app_state.index;
+    let previous = fetch_alias_indexes(&app_state.client, alias).await?;
+    if previous.is_empty() && index_exists(&app_state.client, alias).await? {
+        return Err(ValidationError::ConcreteIndex(alias.clone()).into());
+    }
+    let previous_count = match previous.is_empty() {
+        true => 0,
+        false => count_documents(&app_state.client, alias).await?,
+    };
+    check_index_schema(
+        &app_state.client,
+        &params.index,
+        &app_state.languages,
+        &app_state.supported_database_versions,
+    )
+    .await?;
+    let count = count_documents(&app_state.client, &params.index).await?;
+
+    let min = ((previous_count as f64 * (1.0 - max_count_drop)).ceil() as u64).max(1);
+    if count < min {
+        return Err(ValidationError::DocumentCount {
+            index: params.index,
+            count,
+            min,
+        }
+        .into());
+    }
+
+    swap_alias(&app_state.client, alias, &previous, &params.index).await?;
+
+    return Ok(axum::Json(json!({
+        "alias": alias,
+        "index": params.index,
+        "count": count,
+        "previous": previous,
+        "previous_count": previous_count,
+    })));
+}
+
+async fn authenticate(
+    State(app_state): State<AppState>,
+    matched_path: MatchedPath,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, PhotonError> {
+    let endpoint = matched_path.as_str();
+    if PUBLIC_ENDPOINTS.contains(&endpoint) {
+        return Ok(next.run(request).await);
+    }
+
+    let params: Vec<(String, String)> = match axum::extract::Query::try_from_uri(request.uri()) {
+        Ok(axum::extract::Query(params)) => params,
+        Err(_) => vec![],
+    };
+    let param = |name| query_param_values(&params, name);
+
+    let key = request
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .or_else(|| param(API_KEY_PARAM).next());
+    let limits = param("limit")
+        .chain(param("size"))
+        .filter_map(|limit| limit.parse().ok())
+        .collect();
+    let mut languages: Vec<&str> = param("lang").map(|language| language.trim()).collect();
+
+    // Nominatim falls back to the Accept-Language header, check the language it will answer in
+    let accept_language = param("accept-language")
+        .next()
+        .map(|value| value.to_string());
+    let resolved = resolve_language(&accept_language, request.headers(), &app_state.languages);
+    if ACCEPT_LANGUAGE_ENDPOINTS.contains(&endpoint) && resolved != DEFAULT {
+        languages.push(&resolved);
+    }
+
+    app_state.authenticator.authorize(&AuthRequest {
+        key,
+        endpoint,
+        cost: 1,
+        limits,
+        languages,
+    })?;
+    let max_limit = app_state.authenticator.max_limit(key);
+    request.extensions_mut().insert(max_limit);
+
+    return Ok(next.run(request).await);
+}
+
+fn cors_layer(origins: &[HeaderValue]) -> CorsLayer {
+    let allow_origin = if origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(origins.iter().cloned())
+    };
+
+    return CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([Method::GET])
+        .allow_headers([
+            ACCEPT,
+            ACCEPT_LANGUAGE,
+            HeaderName::from_static(API_KEY_HEADER),
+        ])
+        .expose_headers([RETRY_AFTER]);
+}
+
+fn query_param_values<'a>(
+    params: &'a [(String, String)],
+    name: &'a str,
+) -> impl Iterator<Item = &'a str> {
+    return params
+        .iter()
+        .filter(move |(key, _)| key == name)
+        .map(|(_, value)| value.as_str());
+}
+
+#[utoipa::path(
+    get,
+    path = "/search",
+    params(PhotonSearchRequest),
+    responses(
+        (status = 200, description = "Matching places", content(
+            ("application/json" = PhotonResponse),
+            ("text/csv" = String),
+            ("application/x-ndjson" = String),
+        )),
+        (status = 400, description = "Invalid parameter", body = String, content_type = "text/plain"),
+        (status = 401, description = "Missing or invalid API key", body = String, content_type = "text/plain"),
+        (status = 403, description = "API key does not allow the endpoint, limit or language", body = String, content_type = "text/plain"),
+        (status = 429, description = "Rate limit exceeded, retry after the seconds in the Retry-After header", body = String, content_type = "text/plain"),
+        (status = 503, description = "Too many concurrent requests, retry after the seconds in the Retry-After header", body = String, content_type = "text/plain"),
+        (status = 500, description = "Elasticsearch error", body = String, content_type = "text/plain"),
+        (status = 504, description = "Request timed out", body = String, content_type = "text/plain"),
+    )
+)]
 #[debug_handler]
 async fn search(
     State(app_state): State<AppState>,
+    Extension(max_limit): Extension<MaxLimit>,
+    headers: HeaderMap,
     Query(params): Query<PhotonSearchRequest>,
-) -> Result<axum::Json<PhotonResponse>, PhotonError> {
+) -> Result<Response, PhotonError> {
+    let app_state = app_state.with_max_limit(max_limit);
+    let format = resolve_output_format(&params.format, &headers);
+    let extra_columns = params.extra_columns.clone();
+
+    let (result, query) = run_search(&app_state, params).await?;
+
+    return Ok(render_response(result, &format, query, &extra_columns));
+}
+
+async fn run_search(
+    app_state: &AppState,
+    params: PhotonSearchRequest,
+) -> Result<(PhotonResponse, Option<String>), PhotonError> {
     validate_search_request_parameters(&params)?;
     validate_lang_parameter(&params.lang, &app_state.languages)?;
+    validate_geometry_tolerance(&params.geometry_tolerance)?;
 
     let PhotonSearchRequest {
         q,
@@ -107,66 +882,398 @@ async fn search(
         lat,
         limit,
         location_bias_scale,
+        radius,
         bbox,
         zoom,
         osm_tag,
         layer,
-        debug: _, // TODO
+        profile,
+        autocomplete,
+        highlight,
+        format: _,
+        extra_columns: _,
+        geometry,
+        geometry_tolerance,
+        debug,
     } = params;
 
+    let geometry = geometry.unwrap_or_default();
+    let full_geometry = geometry == GeometryDetail::Full;
+    let debug = debug.unwrap_or(false);
+
+    let default_profile = profile.is_none();
+    let profile = validate_profile_parameter(&profile, &app_state.profiles)?;
     let location_bias = validate_location_bias(&lon, &lat, &location_bias_scale, &zoom)?;
-    let envelope = validate_bbox(&bbox)?;
+    let filters = QueryFilters {
+        osm_tags: osm_tag,
+        envelope: validate_bbox(&bbox)?,
+        layers: layer,
+        countries: None,
+        radius,
+    };
     let language = lang.unwrap_or_else(|| DEFAULT.to_string());
     let languages = app_state.languages.clone();
+    let highlight = highlight.unwrap_or(false);
 
-    let mut lenient = false;
-    let mut size = limit.unwrap_or_else(|| 10);
+    let mut size = limit.unwrap_or(10);
     size = if size > 1 {
         (size as f32 * 1.5).round() as i64
     } else {
         size
     };
 
-    let query = build_search_query(
-        &q,
-        &language,
-        &languages,
-        &lenient,
-        &osm_tag,
-        &envelope,
-        &layer,
-        &location_bias,
-    );
+    let q = match q {
+        Some(q) if !q.trim().is_empty() => q,
+        _ => {
+            let query = build_browse_query(&filters, &location_bias, profile);
+            let query = add_geometry_source(query, &full_geometry);
+            let result = app_state.search(query, size, &language).await?;
+            let result = apply_geometry_detail(result, &geometry, &geometry_tolerance);
+            return Ok((result.with_debug(&debug), None));
+        }
+    };
+
+    if autocomplete.unwrap_or(false) {
+        let size = app_state.clamp_size(
+            limit
+                .unwrap_or(AUTOCOMPLETE_MAX_LIMIT)
+                .clamp(1, AUTOCOMPLETE_MAX_LIMIT),
+        );
+        // The completion suggester only matches prefixes ranked by their stored weight, requests
+        // that filter, bias by location, pick a ranking profile, highlight or want the full
+        // geometry use the full query
+        let unfiltered = filters.is_empty()
+            && location_bias.is_none()
+            && default_profile
+            && !highlight
+            && !full_geometry;
+
+        let result = match &app_state.autocomplete_suggest_field {
+            Some(field) if unfiltered => {
+                let query = build_autocomplete_suggest_query(&q, field, &size);
+                app_state.suggest(query, &language).await?
+            }
+            _ => {
+                let query = build_autocomplete_query(
+                    &q,
+                    &language,
+                    &languages,
+                    &filters,
+                    &location_bias,
+                    profile,
+                );
+                let query = add_highlight(query, &language, &highlight);
+                let query = add_geometry_source(query, &full_geometry);
+                app_state.search(query, size, &language).await?
+            }
+        };
 
-    let mut result = send_photon_query(&app_state.client, query, size, &language).await?;
+        let result = apply_geometry_detail(result, &geometry, &geometry_tolerance);
 
-    result = if result.features.is_empty() {
-        lenient = true;
+        return Ok((result.with_debug(&debug), Some(q)));
+    }
+
+    let build_query = |lenient: bool| {
         let query = build_search_query(
             &q,
             &language,
             &languages,
             &lenient,
-            &osm_tag,
-            &envelope,
-            &layer,
+            &filters,
             &location_bias,
+            profile,
         );
-        send_photon_query(&app_state.client, query, size, &language).await?
-    } else {
-        result
+        let query = add_highlight(query, &language, &highlight);
+        add_geometry_source(query, &full_geometry)
     };
 
-    return Ok(axum::Json::from(result));
+    let result = send_search_query(app_state, build_query, size, &language).await?;
+
+    let result = apply_geometry_detail(result, &geometry, &geometry_tolerance);
+
+    return Ok((result.with_debug(&debug), Some(q)));
+}
+
+async fn send_search_query<F>(
+    app_state: &AppState,
+    build_query: F,
+    size: i64,
+    language: &str,
+) -> Result<PhotonResponse, PhotonError>
+where
+    F: Fn(bool) -> Search,
+{
+    let result = match app_state.lenient_search_mode {
+        LenientSearchMode::Sequential => {
+            let strict = app_state.search(build_query(false), size, language).await?;
+            if strict.features.is_empty() {
+                let lenient = app_state.search(build_query(true), size, language).await?;
+                strict_or_lenient(strict, lenient)
+            } else {
+                strict
+            }
+        }
+        LenientSearchMode::Concurrent => {
+            let strict = app_state.search(build_query(false), size, language);
+            let lenient = app_state.search(build_query(true), size, language);
+            tokio::pin!(strict, lenient);
+
+            // Drive both searches but only wait for the strict one, the lenient search is dropped
+            // as soon as it is not needed and its errors only matter when it is used
+            let mut lenient_result = None;
+            let strict = loop {
+                tokio::select! {
+                    result = &mut strict => break result?,
+                    result = &mut lenient, if lenient_result.is_none() => lenient_result = Some(result),
+                }
+            };
+            if !strict.features.is_empty() {
+                return Ok(strict);
+            }
+
+            let lenient = match lenient_result {
+                Some(result) => result?,
+                None => lenient.await?,
+            };
+            strict_or_lenient(strict, lenient)
+        }
+        LenientSearchMode::MultiSearch => {
+            let mut responses = app_state
+                .multi_search(vec![build_query(false), build_query(true)], size, language)
+                .await?;
+            let lenient = responses.pop().flatten();
+            let strict = responses.pop().flatten();
+
+            // Searches that failed inside the msearch are repeated on their own to get their error
+            let strict = match strict {
+                Some(strict) => strict,
+                None => app_state.search(build_query(false), size, language).await?,
+            };
+            if !strict.features.is_empty() {
+                return Ok(strict);
+            }
+
+            let lenient = match lenient {
+                Some(lenient) => lenient,
+                None => app_state.search(build_query(true), size, language).await?,
+            };
+            strict_or_lenient(strict, lenient)
+        }
+    };
+
+    return Ok(result);
+}
+
+fn strict_or_lenient(strict: PhotonResponse, mut lenient: PhotonResponse) -> PhotonResponse {
+    if !strict.features.is_empty() {
+        return strict;
+    }
+    lenient.skipped.extend(strict.skipped);
+    return lenient.into_lenient();
+}
+
+#[utoipa::path(
+    get,
+    path = "/ws/autocomplete",
+    params(PhotonSessionRequest),
+    responses(
+        (status = 101, description = "WebSocket session. Each text message is a search request with a \"seq\" number, answered with the same \"seq\" and the features or an \"error\". A new message cancels the pending search"),
+        (status = 401, description = "Missing or invalid API key", body = String, content_type = "text/plain"),
+        (status = 403, description = "API key does not allow the endpoint", body = String, content_type = "text/plain"),
+        (status = 429, description = "Rate limit exceeded, retry after the seconds in the Retry-After header", body = String, content_type = "text/plain"),
+    )
+)]
+#[debug_handler]
+async fn autocomplete_session(
+    State(app_state): State<AppState>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+    Query(params): Query<PhotonSessionRequest>,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    let debounce = params
+        .debounce
+        .unwrap_or(SESSION_DEFAULT_DEBOUNCE)
+        .min(SESSION_MAX_DEBOUNCE);
+    let key = headers
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(|key| key.to_string())
+        .or(params.api_key);
+    let client = client_identity(
+        key.as_deref(),
+        connect_info.map(|ConnectInfo(address)| address),
+        &app_state.authenticator,
+    );
+    let app_state = app_state.with_max_limit(app_state.authenticator.max_limit(key.as_deref()));
+
+    return upgrade.on_upgrade(move |socket| {
+        run_autocomplete_session(
+            socket,
+            app_state,
+            key,
+            client,
+            Duration::from_millis(debounce),
+        )
+    });
+}
+
+async fn run_autocomplete_session(
+    mut socket: WebSocket,
+    app_state: AppState,
+    key: Option<String>,
+    client: String,
+    debounce: Duration,
+) {
+    let (result_sender, mut results) = mpsc::channel::<PhotonSessionResponse>(16);
+    let mut in_flight: Option<JoinHandle<()>> = None;
+
+    loop {
+        tokio::select! {
+            message = socket.recv() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                };
+
+                // a new keystroke supersedes the pending or running search, aborting it
+                // drops the Elasticsearch request future
+                if let Some(task) = in_flight.take() {
+                    task.abort();
+                }
+
+                let PhotonSessionMessage { seq, mut request } = match serde_json::from_str(&text) {
+                    Ok(message) => message,
+                    Err(err) => {
+                        let response = PhotonSessionResponse {
+                            seq: None,
+                            response: None,
+                            error: Some(err.to_string()),
+                        };
+                        if send_session_response(&mut socket, &response).await.is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+                };
+                request.autocomplete = Some(true);
+
+                // Every message is a search of its own and counts against the key's limits and the
+                // client's rate limit bucket
+                let authorized = app_state.authenticator.authorize(&AuthRequest {
+                    key: key.as_deref(),
+                    endpoint: SESSION_ENDPOINT,
+                    cost: 1,
+                    limits: request.limit.into_iter().collect(),
+                    languages: request.lang.iter().map(|lang| lang.trim()).collect(),
+                })
+                .map_err(PhotonError::from)
+                .and_then(|_| {
+                    return app_state
+                        .acquire_rate_limit(&client)
+                        .map_err(PhotonError::from);
+                });
+                if let Err(err) = authorized {
+                    let response = PhotonSessionResponse {
+                        seq: Some(seq),
+                        response: None,
+                        error: Some(err.to_string()),
+                    };
+                    if send_session_response(&mut socket, &response).await.is_err() {
+                        break;
+                    }
+                    continue;
+                }
+
+                let app_state = app_state.clone();
+                let result_sender = result_sender.clone();
+                in_flight = Some(tokio::spawn(async move {
+                    tokio::time::sleep(debounce).await;
+                    let response = match run_search(&app_state, request).await {
+                        Ok((response, _)) => PhotonSessionResponse {
+                            seq: Some(seq),
+                            response: Some(response),
+                            error: None,
+                        },
+                        Err(err) => PhotonSessionResponse {
+                            seq: Some(seq),
+                            response: None,
+                            error: Some(err.to_string()),
+                        },
+                    };
+                    let _ = result_sender.send(response).await;
+                }));
+            }
+            Some(response) = results.recv() => {
+                if send_session_response(&mut socket, &response).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    if let Some(task) = in_flight {
+        task.abort();
+    }
+}
+
+async fn send_session_response(
+    socket: &mut WebSocket,
+    response: &PhotonSessionResponse,
+) -> Result<(), axum::Error> {
+    let text = serde_json::to_string(response).unwrap();
+    return socket.send(Message::Text(text)).await;
 }
 
+#[utoipa::path(
+    get,
+    path = "/reverse",
+    params(PhotonReverseRequest),
+    responses(
+        (status = 200, description = "Places near the coordinate", content(
+            ("application/json" = PhotonResponse),
+            ("text/csv" = String),
+            ("application/x-ndjson" = String),
+        )),
+        (status = 400, description = "Invalid parameter", body = String, content_type = "text/plain"),
+        (status = 401, description = "Missing or invalid API key", body = String, content_type = "text/plain"),
+        (status = 403, description = "API key does not allow the endpoint, limit or language", body = String, content_type = "text/plain"),
+        (status = 429, description = "Rate limit exceeded, retry after the seconds in the Retry-After header", body = String, content_type = "text/plain"),
+        (status = 503, description = "Too many concurrent requests, retry after the seconds in the Retry-After header", body = String, content_type = "text/plain"),
+        (status = 500, description = "Elasticsearch error", body = String, content_type = "text/plain"),
+        (status = 504, description = "Request timed out", body = String, content_type = "text/plain"),
+    )
+)]
 #[debug_handler]
 async fn reverse(
     State(app_state): State<AppState>,
+    Extension(max_limit): Extension<MaxLimit>,
+    headers: HeaderMap,
     Query(params): Query<PhotonReverseRequest>,
-) -> Result<axum::Json<PhotonResponse>, PhotonError> {
+) -> Result<Response, PhotonError> {
+    let app_state = app_state.with_max_limit(max_limit);
+    let format = resolve_output_format(&params.format, &headers);
+    let extra_columns = params.extra_columns.clone();
+    let query = format!("{},{}", params.lat, params.lon);
+
+    let result = run_reverse(&app_state, params).await?;
+
+    return Ok(render_response(
+        result,
+        &format,
+        Some(query),
+        &extra_columns,
+    ));
+}
+
+async fn run_reverse(
+    app_state: &AppState,
+    params: PhotonReverseRequest,
+) -> Result<PhotonResponse, PhotonError> {
     validate_reverse_request_parameters(&params)?;
     validate_lang_parameter(&params.lang, &app_state.languages)?;
+    validate_geometry_tolerance(&params.geometry_tolerance)?;
 
     let PhotonReverseRequest {
         lang,
@@ -178,37 +1285,545 @@ async fn reverse(
         limit,
         osm_tag,
         layer,
-        debug: _, // TODO
+        format: _,
+        extra_columns: _,
+        geometry,
+        geometry_tolerance,
+        debug,
     } = params;
 
+    let geometry = geometry.unwrap_or_default();
+    let full_geometry = geometry == GeometryDetail::Full;
+
     let language = lang.unwrap_or_else(|| DEFAULT.to_string());
-    let size = limit.unwrap_or_else(|| 10);
+    let size = limit.unwrap_or(10);
 
     let query = build_reverse_query(
-        &lat,
-        &lon,
+        &Point { x: lon, y: lat },
         &radius,
         &query_string_filter,
-        &distance_sort.unwrap_or_else(|| true),
+        &distance_sort.unwrap_or(true),
         &layer,
         &osm_tag,
+        &None,
     );
+    let query = add_geometry_source(query, &full_geometry);
 
-    let result = send_photon_query(&app_state.client, query, size, &language).await?;
+    let result = app_state.search(query, size, &language).await?;
 
-    return Ok(axum::Json::from(result));
+    let result = apply_geometry_detail(result, &geometry, &geometry_tolerance);
+
+    return Ok(result.with_debug(&debug.unwrap_or(false)));
 }
 
+#[utoipa::path(
+    get,
+    path = "/lookup",
+    params(PhotonLookupRequest),
+    responses(
+        (status = 200, description = "The place, or no features if it does not exist", content(
+            ("application/json" = PhotonResponse),
+            ("text/csv" = String),
+            ("application/x-ndjson" = String),
+        )),
+        (status = 400, description = "Invalid parameter", body = String, content_type = "text/plain"),
+        (status = 401, description = "Missing or invalid API key", body = String, content_type = "text/plain"),
+        (status = 403, description = "API key does not allow the endpoint, limit or language", body = String, content_type = "text/plain"),
+        (status = 429, description = "Rate limit exceeded, retry after the seconds in the Retry-After header", body = String, content_type = "text/plain"),
+        (status = 503, description = "Too many concurrent requests, retry after the seconds in the Retry-After header", body = String, content_type = "text/plain"),
+        (status = 500, description = "Elasticsearch error", body = String, content_type = "text/plain"),
+        (status = 504, description = "Request timed out", body = String, content_type = "text/plain"),
+    )
+)]
 #[debug_handler]
 async fn lookup(
     State(app_state): State<AppState>,
+    headers: HeaderMap,
     Query(params): Query<PhotonLookupRequest>,
-) -> Result<axum::Json<PhotonResponse>, PhotonError> {
+) -> Result<Response, PhotonError> {
+    let format = resolve_output_format(&params.format, &headers);
+    let extra_columns = params.extra_columns.clone();
+    let place_id = params.place_id.clone();
+
+    let result = run_lookup(&app_state, params).await?;
+
+    return Ok(render_response(
+        result,
+        &format,
+        Some(place_id),
+        &extra_columns,
+    ));
+}
+
+async fn run_lookup(
+    app_state: &AppState,
+    params: PhotonLookupRequest,
+) -> Result<PhotonResponse, PhotonError> {
+    validate_lang_parameter(&params.lang, &app_state.languages)?;
+    validate_geometry_tolerance(&params.geometry_tolerance)?;
+
+    let PhotonLookupRequest {
+        place_id,
+        lang,
+        format: _,
+        extra_columns: _,
+        geometry,
+        geometry_tolerance,
+    } = params;
+
+    let geometry = geometry.unwrap_or_default();
+    let full_geometry = geometry == GeometryDetail::Full;
+
+    let language = lang.unwrap_or_else(|| DEFAULT.to_string());
+
+    let result = app_state
+        .lookup(&place_id, &language, &full_geometry)
+        .await?;
+
+    return Ok(apply_geometry_detail(
+        result,
+        &geometry,
+        &geometry_tolerance,
+    ));
+}
+
+#[utoipa::path(
+    get,
+    path = "/similar",
+    params(PhotonSimilarRequest),
+    responses(
+        (status = 200, description = "Places with the same OSM tag near the place", content(
+            ("application/json" = PhotonResponse),
+            ("text/csv" = String),
+            ("application/x-ndjson" = String),
+        )),
+        (status = 400, description = "Invalid parameter", body = String, content_type = "text/plain"),
+        (status = 401, description = "Missing or invalid API key", body = String, content_type = "text/plain"),
+        (status = 403, description = "API key does not allow the endpoint, limit or language", body = String, content_type = "text/plain"),
+        (status = 429, description = "Rate limit exceeded, retry after the seconds in the Retry-After header", body = String, content_type = "text/plain"),
+        (status = 503, description = "Too many concurrent requests, retry after the seconds in the Retry-After header", body = String, content_type = "text/plain"),
+        (status = 500, description = "Elasticsearch error", body = String, content_type = "text/plain"),
+        (status = 504, description = "Request timed out", body = String, content_type = "text/plain"),
+    )
+)]
+#[debug_handler]
+async fn similar(
+    State(app_state): State<AppState>,
+    Extension(max_limit): Extension<MaxLimit>,
+    headers: HeaderMap,
+    Query(params): Query<PhotonSimilarRequest>,
+) -> Result<Response, PhotonError> {
+    let app_state = app_state.with_max_limit(max_limit);
+    validate_similar_request_parameters(&params)?;
     validate_lang_parameter(&params.lang, &app_state.languages)?;
+    validate_geometry_tolerance(&params.geometry_tolerance)?;
+
+    let PhotonSimilarRequest {
+        place_id,
+        lang,
+        radius,
+        limit,
+        format,
+        extra_columns,
+        geometry,
+        geometry_tolerance,
+    } = params;
 
-    let PhotonLookupRequest { place_id, lang } = params;
+    let format = resolve_output_format(&format, &headers);
+    let geometry = geometry.unwrap_or_default();
+    let full_geometry = geometry == GeometryDetail::Full;
 
     let language = lang.unwrap_or_else(|| DEFAULT.to_string());
+    let size = limit.unwrap_or(10);
+
+    let reference = match app_state.fetch_document(&place_id, &false).await? {
+        Some(reference) => reference,
+        None => {
+            let result = PhotonResponse {
+                r#type: "FeatureCollection".to_string(),
+                features: vec![],
+                lenient: false,
+                skipped: vec![],
+                debug: None,
+            };
+            return Ok(render_response(
+                result,
+                &format,
+                Some(place_id),
+                &extra_columns,
+            ));
+        }
+    };
+
+    let query = build_similar_query(
+        &place_id,
+        &reference.osm_key,
+        &reference.osm_value,
+        &reference.coordinate.lat,
+        &reference.coordinate.lon,
+        &radius.unwrap_or(1),
+    );
+    let query = add_geometry_source(query, &full_geometry);
+
+    let result = app_state.search(query, size, &language).await?;
+
+    let result = apply_geometry_detail(result, &geometry, &geometry_tolerance);
+
+    return Ok(render_response(
+        result,
+        &format,
+        Some(place_id),
+        &extra_columns,
+    ));
+}
+
+#[utoipa::path(
+    get,
+    path = "/tiles/{z}/{x}/{y}.mvt",
+    params(
+        ("z" = u32, Path, maximum = 22),
+        ("x" = u32, Path, description = "Tile column, less than 2^z"),
+        ("y" = u32, Path, description = "Tile row, less than 2^z"),
+        PhotonTileRequest,
+    ),
+    responses(
+        (status = 200, description = "Places in the tile as Mapbox Vector Tile points", body = Vec<u8>, content_type = "application/vnd.mapbox-vector-tile"),
+        (status = 400, description = "Invalid parameter", body = String, content_type = "text/plain"),
+        (status = 401, description = "Missing or invalid API key", body = String, content_type = "text/plain"),
+        (status = 403, description = "API key does not allow the endpoint, limit or language", body = String, content_type = "text/plain"),
+        (status = 429, description = "Rate limit exceeded, retry after the seconds in the Retry-After header", body = String, content_type = "text/plain"),
+        (status = 503, description = "Too many concurrent requests, retry after the seconds in the Retry-After header", body = String, content_type = "text/plain"),
+        (status = 500, description = "Elasticsearch error", body = String, content_type = "text/plain"),
+        (status = 504, description = "Request timed out", body = String, content_type = "text/plain"),
+    )
+)]
+#[debug_handler]
+async fn tile(
+    State(app_state): State<AppState>,
+    Extension(max_limit): Extension<MaxLimit>,
+    Path((z, x, y)): Path<(u32, u32, String)>,
+    Query(params): Query<PhotonTileRequest>,
+) -> Result<Response, PhotonError> {
+    let app_state = app_state.with_max_limit(max_limit);
+    validate_lang_parameter(&params.lang, &app_state.languages)?;
+
+    let tile = parse_tile_coordinate(z, x, &y)?;
+    let language = params.lang.unwrap_or_else(|| DEFAULT.to_string());
+    let profile = validate_profile_parameter(&None, &app_state.profiles)?;
+
+    let filters = QueryFilters {
+        envelope: Some(tile_envelope(&tile)),
+        layers: Some(layers_for_tile_zoom(&tile.z)),
+        ..Default::default()
+    };
+    let query = build_browse_query(&filters, &None, profile);
+
+    let result = app_state
+        .search(query, TILE_FEATURE_LIMIT, &language)
+        .await?;
+
+    return Ok(render_tile(&result, &tile));
+}
+
+#[utoipa::path(
+    get,
+    path = "/nominatim/search",
+    params(NominatimSearchRequest),
+    responses(
+        (status = 200, description = "Places in the Nominatim format, a NominatimGeoJsonResponse with `format=geojson`", body = Vec<NominatimPlace>),
+        (status = 400, description = "Invalid parameter", body = String, content_type = "text/plain"),
+        (status = 401, description = "Missing or invalid API key", body = String, content_type = "text/plain"),
+        (status = 403, description = "API key does not allow the endpoint, limit or language", body = String, content_type = "text/plain"),
+        (status = 429, description = "Rate limit exceeded, retry after the seconds in the Retry-After header", body = String, content_type = "text/plain"),
+        (status = 503, description = "Too many concurrent requests, retry after the seconds in the Retry-After header", body = String, content_type = "text/plain"),
+        (status = 500, description = "Elasticsearch error", body = String, content_type = "text/plain"),
+        (status = 504, description = "Request timed out", body = String, content_type = "text/plain"),
+    )
+)]
+#[debug_handler]
+async fn nominatim_search(
+    State(app_state): State<AppState>,
+    Extension(max_limit): Extension<MaxLimit>,
+    headers: HeaderMap,
+    Query(params): Query<NominatimSearchRequest>,
+) -> Result<Response, PhotonError> {
+    let app_state = app_state.with_max_limit(max_limit);
+    let NominatimSearchRequest {
+        q,
+        format,
+        limit,
+        countrycodes,
+        viewbox,
+        bounded,
+        addressdetails,
+        accept_language,
+    } = params;
+
+    let language = resolve_language(&accept_language, &headers, &app_state.languages);
+    let languages = app_state.languages.clone();
+    let profile = validate_profile_parameter(&None, &app_state.profiles)?;
+    let countries = parse_country_codes(&countrycodes);
+    let viewbox = parse_viewbox(&viewbox)?;
+    let size = limit.unwrap_or(10).clamp(1, 40);
+
+    let (envelope, location_bias) = match viewbox {
+        Some(viewbox) if bounded.unwrap_or(0) != 1 => {
+            let lon = (viewbox.min_lon + viewbox.max_lon) / 2.0;
+            let lat = (viewbox.min_lat + viewbox.max_lat) / 2.0;
+            (
+                None,
+                validate_location_bias(&Some(lon), &Some(lat), &None, &None)?,
+            )
+        }
+        viewbox => (viewbox, None),
+    };
+    let filters = QueryFilters {
+        envelope,
+        countries,
+        ..Default::default()
+    };
+
+    let build_query = |lenient: bool| {
+        build_search_query(
+            &q,
+            &language,
+            &languages,
+            &lenient,
+            &filters,
+            &location_bias,
+            profile,
+        )
+    };
+
+    let result = send_search_query(&app_state, build_query, size, &language).await?;
+
+    return Ok(render_search(result, &format, &addressdetails));
+}
+
+#[utoipa::path(
+    get,
+    path = "/nominatim/reverse",
+    params(NominatimReverseRequest),
+    responses(
+        (status = 200, description = "The nearest place in the Nominatim format, a NominatimGeoJsonResponse with `format=geojson` or `{\"error\": \"Unable to geocode\"}` if there is none", body = NominatimPlace),
+        (status = 400, description = "Invalid parameter", body = String, content_type = "text/plain"),
+        (status = 401, description = "Missing or invalid API key", body = String, content_type = "text/plain"),
+        (status = 403, description = "API key does not allow the endpoint, limit or language", body = String, content_type = "text/plain"),
+        (status = 429, description = "Rate limit exceeded, retry after the seconds in the Retry-After header", body = String, content_type = "text/plain"),
+        (status = 503, description = "Too many concurrent requests, retry after the seconds in the Retry-After header", body = String, content_type = "text/plain"),
+        (status = 500, description = "Elasticsearch error", body = String, content_type = "text/plain"),
+        (status = 504, description = "Request timed out", body = String, content_type = "text/plain"),
+    )
+)]
+#[debug_handler]
+async fn nominatim_reverse(
+    State(app_state): State<AppState>,
+    Extension(max_limit): Extension<MaxLimit>,
+    headers: HeaderMap,
+    Query(params): Query<NominatimReverseRequest>,
+) -> Result<Response, PhotonError> {
+    let app_state = app_state.with_max_limit(max_limit);
+    let NominatimReverseRequest {
+        lat,
+        lon,
+        format,
+        zoom,
+        addressdetails,
+        accept_language,
+    } = params;
+
+    validate_lon(&lon)?;
+    validate_lat(&lat)?;
+    let language = resolve_language(&accept_language, &headers, &app_state.languages);
+
+    let query = build_reverse_query(
+        &Point { x: lon, y: lat },
+        &1,
+        &None,
+        &true,
+        &layer_for_zoom(&zoom),
+        &None,
+        &None,
+    );
+
+    let result = app_state.search(query, 1, &language).await?;
+
+    return Ok(render_reverse(result, &format, &addressdetails));
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/search",
+    params(PeliasSearchRequest),
+    responses(
+        (status = 200, description = "Places in the Pelias format", body = PeliasResponse),
+        (status = 400, description = "Invalid parameter", body = String, content_type = "text/plain"),
+        (status = 401, description = "Missing or invalid API key", body = String, content_type = "text/plain"),
+        (status = 403, description = "API key does not allow the endpoint, limit or language", body = String, content_type = "text/plain"),
+        (status = 429, description = "Rate limit exceeded, retry after the seconds in the Retry-After header", body = String, content_type = "text/plain"),
+        (status = 503, description = "Too many concurrent requests, retry after the seconds in the Retry-After header", body = String, content_type = "text/plain"),
+        (status = 500, description = "Elasticsearch error", body = String, content_type = "text/plain"),
+        (status = 504, description = "Request timed out", body = String, content_type = "text/plain"),
+    )
+)]
+#[debug_handler]
+async fn pelias_search(
+    State(app_state): State<AppState>,
+    Extension(max_limit): Extension<MaxLimit>,
+    Query(params): Query<PeliasSearchRequest>,
+) -> Result<axum::Json<PeliasResponse>, PhotonError> {
+    let app_state = app_state.with_max_limit(max_limit);
+    return pelias_text_search(&app_state, &params, false).await;
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/autocomplete",
+    params(PeliasSearchRequest),
+    responses(
+        (status = 200, description = "Places matching the text as a prefix, in the Pelias format", body = PeliasResponse),
+        (status = 400, description = "Invalid parameter", body = String, content_type = "text/plain"),
+        (status = 401, description = "Missing or invalid API key", body = String, content_type = "text/plain"),
+        (status = 403, description = "API key does not allow the endpoint, limit or language", body = String, content_type = "text/plain"),
+        (status = 429, description = "Rate limit exceeded, retry after the seconds in the Retry-After header", body = String, content_type = "text/plain"),
+        (status = 503, description = "Too many concurrent requests, retry after the seconds in the Retry-After header", body = String, content_type = "text/plain"),
+        (status = 500, description = "Elasticsearch error", body = String, content_type = "text/plain"),
+        (status = 504, description = "Request timed out", body = String, content_type = "text/plain"),
+    )
+)]
+#[debug_handler]
+async fn pelias_autocomplete(
+    State(app_state): State<AppState>,
+    Extension(max_limit): Extension<MaxLimit>,
+    Query(params): Query<PeliasSearchRequest>,
+) -> Result<axum::Json<PeliasResponse>, PhotonError> {
+    let app_state = app_state.with_max_limit(max_limit);
+    return pelias_text_search(&app_state, &params, true).await;
+}
+
+// Search and autocomplete take the same parameters and only differ in the query they send
+async fn pelias_text_search(
+    app_state: &AppState,
+    params: &PeliasSearchRequest,
+    autocomplete: bool,
+) -> Result<axum::Json<PeliasResponse>, PhotonError> {
+    validate_text(&params.text)?;
+    validate_lang_parameter(&params.lang, &app_state.languages)?;
+
+    let filters = QueryFilters {
+        envelope: parse_boundary_rect(params)?,
+        layers: parse_layers(&params.layers)?,
+        countries: parse_boundary_country(&params.boundary_country)?,
+        ..Default::default()
+    };
+    let location_bias = validate_location_bias(
+        &params.focus_point_lon,
+        &params.focus_point_lat,
+        &None,
+        &None,
+    )?;
+    let profile = validate_profile_parameter(&None, &app_state.profiles)?;
+    let language = params.lang.clone().unwrap_or_else(|| DEFAULT.to_string());
+
+    let (size, result) = if autocomplete {
+        let size = app_state.clamp_size(
+            params
+                .size
+                .unwrap_or(AUTOCOMPLETE_MAX_LIMIT)
+                .clamp(1, AUTOCOMPLETE_MAX_LIMIT),
+        );
+        let query = build_autocomplete_query(
+            &params.text,
+            &language,
+            &app_state.languages,
+            &filters,
+            &location_bias,
+            profile,
+        );
+        let result = app_state.search(query, size, &language).await?;
+        (size, result)
+    } else {
+        let size = app_state.clamp_size(params.size.unwrap_or(10).clamp(1, 40));
+        let build_query = |lenient: bool| {
+            build_search_query(
+                &params.text,
+                &language,
+                &app_state.languages,
+                &lenient,
+                &filters,
+                &location_bias,
+                profile,
+            )
+        };
+        let result = send_search_query(app_state, build_query, size, &language).await?;
+        (size, result)
+    };
+
+    let query = json!({
+        "text": params.text,
+        "size": size,
+        "layers": params.layers,
+        "lang": language,
+    });
+
+    return Ok(axum::Json::from(to_pelias(result, query)));
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/reverse",
+    params(PeliasReverseRequest),
+    responses(
+        (status = 200, description = "Places near the point in the Pelias format", body = PeliasResponse),
+        (status = 400, description = "Invalid parameter", body = String, content_type = "text/plain"),
+        (status = 401, description = "Missing or invalid API key", body = String, content_type = "text/plain"),
+        (status = 403, description = "API key does not allow the endpoint, limit or language", body = String, content_type = "text/plain"),
+        (status = 429, description = "Rate limit exceeded, retry after the seconds in the Retry-After header", body = String, content_type = "text/plain"),
+        (status = 503, description = "Too many concurrent requests, retry after the seconds in the Retry-After header", body = String, content_type = "text/plain"),
+        (status = 500, description = "Elasticsearch error", body = String, content_type = "text/plain"),
+        (status = 504, description = "Request timed out", body = String, content_type = "text/plain"),
+    )
+)]
+#[debug_handler]
+async fn pelias_reverse(
+    State(app_state): State<AppState>,
+    Extension(max_limit): Extension<MaxLimit>,
+    Query(params): Query<PeliasReverseRequest>,
+) -> Result<axum::Json<PeliasResponse>, PhotonError> {
+    let app_state = app_state.with_max_limit(max_limit);
+    validate_lang_parameter(&params.lang, &app_state.languages)?;
+    validate_lon(&params.point_lon)?;
+    validate_lat(&params.point_lat)?;
+
+    let layers = parse_layers(&params.layers)?;
+    let countries = parse_boundary_country(&params.boundary_country)?;
+    let language = params.lang.clone().unwrap_or_else(|| DEFAULT.to_string());
+    let size = app_state.clamp_size(params.size.unwrap_or(10).clamp(1, 40));
+    let radius = params.boundary_circle_radius.unwrap_or(1);
+
+    let query = build_reverse_query(
+        &Point {
+            x: params.point_lon,
+            y: params.point_lat,
+        },
+        &radius,
+        &None,
+        &true,
+        &layers,
+        &None,
+        &countries,
+    );
+
+    let result = app_state.search(query, size, &language).await?;
+
+    let query = json!({
+        "point.lat": params.point_lat,
+        "point.lon": params.point_lon,
+        "boundary.circle.radius": radius,
+        "size": size,
+        "layers": params.layers,
+        "lang": language,
+    });
 
-    return send_lookup(&app_state.client, &place_id, &language).await;
+    return Ok(axum::Json::from(to_pelias(result, query)));
 }
@@ -0,0 +1,51 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+
+pub const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+#[derive(Default)]
+pub struct Metrics {
+    counters: Mutex<BTreeMap<String, BTreeMap<String, u64>>>,
+}
+
+impl Metrics {
+    pub fn increment(&self, name: &str, labels: &[(&str, &str)]) {
+        self.add(name, labels, 1);
+    }
+
+    pub fn add(&self, name: &str, labels: &[(&str, &str)], value: u64) {
+        let labels = labels
+            .iter()
+            .map(|(key, value)| format!("{}=\"{}\"", key, escape_label(value)))
+            .collect::<Vec<String>>()
+            .join(",");
+
+        let mut counters = self.counters.lock().unwrap();
+        *counters
+            .entry(name.to_string())
+            .or_default()
+            .entry(labels)
+            .or_default() += value;
+    }
+
+    pub fn render(&self) -> String {
+        let counters = self.counters.lock().unwrap();
+
+        let mut output = String::new();
+        for (name, series) in counters.iter() {
+            writeln!(output, "# TYPE {} counter", name).unwrap();
+            for (labels, value) in series {
+                writeln!(output, "{}{{{}}} {}", name, labels, value).unwrap();
+            }
+        }
+        return output;
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    return value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+}
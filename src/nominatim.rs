@@ -0,0 +1,450 @@
+use axum::http::header::ACCEPT_LANGUAGE;
+use axum::http::HeaderMap;
+use axum::response::{IntoResponse, Response};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, HashSet};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::address_format::format_label;
+use crate::errors::ValidationError;
+use crate::query::Envelope;
+use crate::response::{osm_type_name, PhotonGeometry, PhotonResponse, PhotonResponseProperties};
+use crate::validation::validate_bbox;
+
+const LICENCE: &str = "Data © OpenStreetMap contributors, ODbL 1.0. https://osm.org/copyright";
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct NominatimSearchRequest {
+    /// Free text query
+    pub q: String,
+    /// Output format, defaults to `jsonv2`
+    pub format: Option<NominatimFormat>,
+    /// Maximum number of results, at most 40
+    pub limit: Option<i64>,
+    /// Comma separated ISO 3166-1 alpha-2 country codes
+    pub countrycodes: Option<String>,
+    /// Preferred area as `x1,y1,x2,y2`
+    pub viewbox: Option<String>,
+    /// Only return results within `viewbox` when set to 1
+    #[param(maximum = 1)]
+    pub bounded: Option<u8>,
+    /// Include the address breakdown when set to 1
+    #[param(maximum = 1)]
+    pub addressdetails: Option<u8>,
+    /// Preferred languages, overrides the `Accept-Language` header
+    #[serde(rename = "accept-language")]
+    pub accept_language: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct NominatimReverseRequest {
+    #[param(minimum = -90, maximum = 90)]
+    pub lat: f32,
+    #[param(minimum = -180, maximum = 180)]
+    pub lon: f32,
+    /// Output format, defaults to `jsonv2`
+    pub format: Option<NominatimFormat>,
+    /// Level of detail, lower zoom levels return streets, cities or countries instead of houses
+    #[param(maximum = 18)]
+    pub zoom: Option<i64>,
+    /// Include the address breakdown unless set to 0
+    #[param(maximum = 1)]
+    pub addressdetails: Option<u8>,
+    /// Preferred languages, overrides the `Accept-Language` header
+    #[serde(rename = "accept-language")]
+    pub accept_language: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum NominatimFormat {
+    Json,
+    #[default]
+    Jsonv2,
+    GeoJson,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct NominatimPlace {
+    pub place_id: i64,
+    pub licence: String,
+    pub osm_type: String,
+    pub osm_id: i64,
+    pub boundingbox: [String; 4],
+    pub lat: String,
+    pub lon: String,
+    pub display_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    pub r#type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub addresstype: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct NominatimGeoJsonResponse {
+    pub r#type: String,
+    pub licence: String,
+    pub features: Vec<NominatimGeoJsonFeature>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct NominatimGeoJsonFeature {
+    pub r#type: String,
+    pub properties: NominatimGeoJsonProperties,
+    pub bbox: [f32; 4],
+    pub geometry: PhotonGeometry,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct NominatimGeoJsonProperties {
+    pub place_id: i64,
+    pub osm_type: String,
+    pub osm_id: i64,
+    pub display_name: String,
+    pub category: String,
+    pub r#type: String,
+    pub addresstype: String,
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<BTreeMap<String, String>>,
+}
+
+pub fn resolve_language(
+    accept_language: &Option<String>,
+    headers: &HeaderMap,
+    languages: &[String],
+) -> String {
+    let header = headers
+        .get(ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok());
+
+    let preferences = match accept_language {
+        Some(accept_language) => Some(accept_language.as_str()),
+        None => header,
+    };
+
+    if let Some(preferences) = preferences {
+        for preference in preferences.split(',') {
+            let tag = preference.split(';').next().unwrap_or("").trim();
+            let primary = tag.split('-').next().unwrap_or("").to_lowercase();
+            if languages.contains(&primary) {
+                return primary;
+            }
+        }
+    }
+
+    return "default".to_string();
+}
+
+pub fn parse_country_codes(countrycodes: &Option<String>) -> Option<HashSet<String>> {
+    let countries: HashSet<String> = countrycodes
+        .as_deref()?
+        .split(',')
+        .map(|c| c.trim().to_uppercase())
+        .filter(|c| !c.is_empty())
+        .collect();
+
+    if countries.is_empty() {
+        return None;
+    }
+
+    return Some(countries);
+}
+
+pub fn parse_viewbox(viewbox: &Option<String>) -> Result<Option<Envelope>, ValidationError> {
+    let viewbox = match viewbox {
+        Some(viewbox) => viewbox,
+        None => return Ok(None),
+    };
+
+    let values: Vec<f32> = viewbox
+        .split(',')
+        .map(|v| v.trim().parse::<f32>())
+        .collect::<Result<Vec<f32>, _>>()
+        .map_err(|_| ValidationError::Viewbox(viewbox.clone()))?;
+
+    if values.len() != 4 {
+        return Err(ValidationError::Viewbox(viewbox.clone()));
+    }
+
+    return validate_bbox(&Some([
+        values[0].min(values[2]),
+        values[1].min(values[3]),
+        values[0].max(values[2]),
+        values[1].max(values[3]),
+    ]));
+}
+
+pub fn layer_for_zoom(zoom: &Option<i64>) -> Option<HashSet<String>> {
+    let layer = match zoom {
+        Some(zoom) if *zoom <= 3 => "country",
+        Some(zoom) if *zoom <= 5 => "state",
+        Some(zoom) if *zoom <= 8 => "county",
+        Some(zoom) if *zoom <= 10 => "city",
+        Some(zoom) if *zoom <= 14 => "district",
+        Some(zoom) if *zoom <= 17 => "street",
+        _ => return None,
+    };
+
+    return Some(HashSet::from([layer.to_string()]));
+}
+
+pub fn render_search(
+    response: PhotonResponse,
+    format: &Option<NominatimFormat>,
+    addressdetails: &Option<u8>,
+) -> Response {
+    let format = format.unwrap_or_default();
+    let addressdetails = addressdetails.unwrap_or(0) == 1;
+
+    if format == NominatimFormat::GeoJson {
+        return axum::Json::from(to_geojson(&response, addressdetails)).into_response();
+    }
+
+    let places: Vec<NominatimPlace> = response
+        .features
+        .iter()
+        .map(|feature| to_place(&feature.properties, &format, addressdetails))
+        .collect();
+
+    return axum::Json::from(places).into_response();
+}
+
+pub fn render_reverse(
+    response: PhotonResponse,
+    format: &Option<NominatimFormat>,
+    addressdetails: &Option<u8>,
+) -> Response {
+    let format = format.unwrap_or_default();
+    let addressdetails = addressdetails.unwrap_or(1) == 1;
+
+    if format == NominatimFormat::GeoJson {
+        return axum::Json::from(to_geojson(&response, addressdetails)).into_response();
+    }
+
+    return match response.features.first() {
+        Some(feature) => {
+            axum::Json::from(to_place(&feature.properties, &format, addressdetails)).into_response()
+        }
+        None => axum::Json::from(json!({ "error": "Unable to geocode" })).into_response(),
+    };
+}
+
+fn to_place(
+    properties: &PhotonResponseProperties,
+    format: &NominatimFormat,
+    addressdetails: bool,
+) -> NominatimPlace {
+    let jsonv2 = *format == NominatimFormat::Jsonv2;
+    let [min_lon, min_lat, max_lon, max_lat] = bounding_box(properties);
+
+    return NominatimPlace {
+        place_id: properties.place_id,
+        licence: LICENCE.to_string(),
+        osm_type: osm_type_name(&properties.osm_type).to_string(),
+        osm_id: properties.osm_id,
+        boundingbox: [
+            min_lat.to_string(),
+            max_lat.to_string(),
+            min_lon.to_string(),
+            max_lon.to_string(),
+        ],
+        lat: properties.geometry.coordinates[1].to_string(),
+        lon: properties.geometry.coordinates[0].to_string(),
+        display_name: display_name(properties),
+        class: if jsonv2 {
+            None
+        } else {
+            Some(properties.osm_key.clone())
+        },
+        category: if jsonv2 {
+            Some(properties.osm_key.clone())
+        } else {
+            None
+        },
+        r#type: properties.osm_value.clone(),
+        addresstype: if jsonv2 {
+            Some(properties.r#type.clone())
+        } else {
+            None
+        },
+        name: if jsonv2 {
+            properties.name.clone()
+        } else {
+            None
+        },
+        address: if addressdetails {
+            Some(address(properties))
+        } else {
+            None
+        },
+    };
+}
+
+fn to_geojson(response: &PhotonResponse, addressdetails: bool) -> NominatimGeoJsonResponse {
+    return NominatimGeoJsonResponse {
+        r#type: "FeatureCollection".to_string(),
+        licence: LICENCE.to_string(),
+        features: response
+            .features
+            .iter()
+            .map(|feature| {
+                let properties = &feature.properties;
+                NominatimGeoJsonFeature {
+                    r#type: "Feature".to_string(),
+                    properties: NominatimGeoJsonProperties {
+                        place_id: properties.place_id,
+                        osm_type: osm_type_name(&properties.osm_type).to_string(),
+                        osm_id: properties.osm_id,
+                        display_name: display_name(properties),
+                        category: properties.osm_key.clone(),
+                        r#type: properties.osm_value.clone(),
+                        addresstype: properties.r#type.clone(),
+                        name: properties.name.clone(),
+                        address: if addressdetails {
+                            Some(address(properties))
+                        } else {
+                            None
+                        },
+                    },
+                    bbox: bounding_box(properties),
+                    geometry: PhotonGeometry {
+                        r#type: properties.geometry.r#type.clone(),
+                        coordinates: properties.geometry.coordinates,
+                    },
+                }
+            })
+            .collect(),
+    };
+}
+
+fn display_name(properties: &PhotonResponseProperties) -> String {
+    return format_label(properties)
+        .or_else(|| properties.name.clone())
+        .unwrap_or_default();
+}
+
+fn bounding_box(properties: &PhotonResponseProperties) -> [f32; 4] {
+    return match properties.extent {
+        Some([min_lon, max_lat, max_lon, min_lat]) => [min_lon, min_lat, max_lon, max_lat],
+        None => {
+            let [lon, lat] = properties.geometry.coordinates;
+            [lon, lat, lon, lat]
+        }
+    };
+}
+
+fn address(properties: &PhotonResponseProperties) -> BTreeMap<String, String> {
+    let mut address = BTreeMap::new();
+
+    for (key, value) in [
+        ("house_number", &properties.housenumber),
+        ("road", &properties.street),
+        ("neighbourhood", &properties.locality),
+        ("suburb", &properties.district),
+        ("city", &properties.city),
+        ("county", &properties.county),
+        ("state", &properties.state),
+        ("postcode", &properties.postcode),
+        ("country", &properties.country),
+    ] {
+        if let Some(value) = value {
+            address.insert(key.to_string(), value.clone());
+        }
+    }
+
+    if let Some(countrycode) = &properties.countrycode {
+        address.insert("country_code".to_string(), countrycode.to_lowercase());
+    }
+
+    return address;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn some(value: &str) -> Option<String> {
+        return Some(value.to_string());
+    }
+
+    #[test]
+    fn parses_country_codes() {
+        let countries = parse_country_codes(&some("de, at,,ch ")).unwrap();
+
+        assert_eq!(
+            countries,
+            HashSet::from(["DE".to_string(), "AT".to_string(), "CH".to_string()])
+        );
+    }
+
+    #[test]
+    fn empty_country_codes_are_ignored() {
+        assert!(parse_country_codes(&None).is_none());
+        assert!(parse_country_codes(&some("")).is_none());
+        assert!(parse_country_codes(&some(" , ")).is_none());
+    }
+
+    #[test]
+    fn parses_viewbox_in_any_corner_order() {
+        for viewbox in ["13.0,52.6,13.8,52.3", "13.8,52.3,13.0,52.6"] {
+            let envelope = parse_viewbox(&some(viewbox)).unwrap().unwrap();
+
+            assert_eq!(envelope.min_lon, 13.0);
+            assert_eq!(envelope.min_lat, 52.3);
+            assert_eq!(envelope.max_lon, 13.8);
+            assert_eq!(envelope.max_lat, 52.6);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_viewbox() {
+        assert!(parse_viewbox(&None).unwrap().is_none());
+        assert!(parse_viewbox(&some("13.0,52.6,13.8")).is_err());
+        assert!(parse_viewbox(&some("13.0,52.6,13.8,52.3,1")).is_err());
+        assert!(parse_viewbox(&some("13.0,north,13.8,52.3")).is_err());
+        assert!(parse_viewbox(&some("13.0,95.0,13.8,52.3")).is_err());
+        assert!(parse_viewbox(&some("-190.0,52.6,13.8,52.3")).is_err());
+    }
+
+    #[test]
+    fn resolves_language_from_param_before_header() {
+        let languages = vec!["de".to_string(), "en".to_string()];
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT_LANGUAGE, "en-US,en;q=0.9".parse().unwrap());
+
+        assert_eq!(resolve_language(&None, &headers, &languages), "en");
+        assert_eq!(
+            resolve_language(&some("fr, de-AT;q=0.8"), &headers, &languages),
+            "de"
+        );
+        assert_eq!(
+            resolve_language(&some("fr"), &HeaderMap::new(), &languages),
+            "default"
+        );
+    }
+
+    #[test]
+    fn maps_zoom_to_layer() {
+        assert_eq!(
+            layer_for_zoom(&Some(3)),
+            Some(HashSet::from(["country".to_string()]))
+        );
+        assert_eq!(
+            layer_for_zoom(&Some(16)),
+            Some(HashSet::from(["street".to_string()]))
+        );
+        assert!(layer_for_zoom(&Some(18)).is_none());
+        assert!(layer_for_zoom(&None).is_none());
+    }
+}
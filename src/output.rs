@@ -0,0 +1,203 @@
+use axum::http::header::{ACCEPT, CONTENT_TYPE};
+use axum::http::HeaderMap;
+use axum::response::{IntoResponse, Response};
+
+use crate::address_format::format_label;
+use crate::geocodejson::to_geocodejson;
+use crate::request::OutputFormat;
+use crate::response::{PhotonResponse, PhotonResponseProperties};
+
+const CSV_CONTENT_TYPE: &str = "text/csv; charset=utf-8";
+const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+const CSV_COLUMNS: [&str; 24] = [
+    "place_id",
+    "parent_place_id",
+    "osm_type",
+    "osm_id",
+    "osm_key",
+    "osm_value",
+    "type",
+    "name",
+    "housenumber",
+    "street",
+    "locality",
+    "district",
+    "postcode",
+    "city",
+    "county",
+    "state",
+    "country",
+    "countrycode",
+    "label",
+    "lon",
+    "lat",
+    "extent",
+    "score",
+    "match_type",
+];
+
+pub fn resolve_output_format(format: &Option<OutputFormat>, headers: &HeaderMap) -> OutputFormat {
+    if let Some(format) = format {
+        return *format;
+    }
+
+    let accept = match headers.get(ACCEPT).and_then(|value| value.to_str().ok()) {
+        Some(accept) => accept,
+        None => return OutputFormat::default(),
+    };
+
+    for media_type in accept.split(',') {
+        let media_type = media_type.split(';').next().unwrap_or("").trim();
+        match media_type.to_ascii_lowercase().as_str() {
+            "text/csv" => return OutputFormat::Csv,
+            "application/x-ndjson" | "application/ndjson" => return OutputFormat::NdJson,
+            "application/geo+json" | "application/json" => return OutputFormat::GeoJson,
+            _ => continue,
+        }
+    }
+
+    return OutputFormat::default();
+}
+
+pub fn render_response(
+    response: PhotonResponse,
+    format: &OutputFormat,
+    query: Option<String>,
+    extra_columns: &Option<String>,
+) -> Response {
+    return match format {
+        OutputFormat::GeoJson => axum::Json::from(response).into_response(),
+        OutputFormat::GeocodeJson => {
+            axum::Json::from(to_geocodejson(response, query)).into_response()
+        }
+        OutputFormat::Label => {
+            // One entry per feature keeps the labels aligned with the results of other formats
+            let labels: Vec<String> = response
+                .features
+                .iter()
+                .map(|feature| {
+                    return format_label(&feature.properties)
+                        .or_else(|| feature.properties.name.clone())
+                        .unwrap_or_default();
+                })
+                .collect();
+            axum::Json::from(labels).into_response()
+        }
+        OutputFormat::Csv => {
+            let body = render_csv(&response, &parse_extra_columns(extra_columns));
+            ([(CONTENT_TYPE, CSV_CONTENT_TYPE)], body).into_response()
+        }
+        OutputFormat::NdJson => {
+            let body = render_ndjson(&response);
+            ([(CONTENT_TYPE, NDJSON_CONTENT_TYPE)], body).into_response()
+        }
+    };
+}
+
+fn parse_extra_columns(extra_columns: &Option<String>) -> Vec<String> {
+    let mut columns: Vec<String> = vec![];
+    if let Some(extra_columns) = extra_columns {
+        for column in extra_columns.split(',').map(|column| column.trim()) {
+            if !column.is_empty() && !columns.iter().any(|existing| existing == column) {
+                columns.push(column.to_string());
+            }
+        }
+    }
+    return columns;
+}
+
+fn render_csv(response: &PhotonResponse, extra_columns: &Vec<String>) -> Vec<u8> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+
+    let mut header: Vec<String> = CSV_COLUMNS
+        .iter()
+        .map(|column| column.to_string())
+        .collect();
+    header.extend(
+        extra_columns
+            .iter()
+            .map(|column| format!("extra.{}", column)),
+    );
+    writer.write_record(&header).unwrap();
+
+    for feature in &response.features {
+        writer
+            .write_record(csv_record(&feature.properties, extra_columns))
+            .unwrap();
+    }
+
+    return writer.into_inner().unwrap();
+}
+
+fn csv_record(properties: &PhotonResponseProperties, extra_columns: &Vec<String>) -> Vec<String> {
+    let text = |value: &Option<String>| value.clone().unwrap_or_default();
+
+    let [lon, lat] = properties.geometry.coordinates;
+    let extent = match properties.extent {
+        Some(extent) => extent
+            .iter()
+            .map(|value| value.to_string())
+            .collect::<Vec<String>>()
+            .join(" "),
+        None => String::new(),
+    };
+    let match_type = match properties.match_type {
+        Some(match_type) => match_type.as_str(),
+        None => "",
+    };
+
+    let mut record = vec![
+        properties.place_id.to_string(),
+        properties
+            .parent_place_id
+            .map(|id| id.to_string())
+            .unwrap_or_default(),
+        properties.osm_type.clone(),
+        properties.osm_id.to_string(),
+        properties.osm_key.clone(),
+        properties.osm_value.clone(),
+        properties.r#type.clone(),
+        text(&properties.name),
+        text(&properties.housenumber),
+        text(&properties.street),
+        text(&properties.locality),
+        text(&properties.district),
+        text(&properties.postcode),
+        text(&properties.city),
+        text(&properties.county),
+        text(&properties.state),
+        text(&properties.country),
+        text(&properties.countrycode),
+        format_label(properties).unwrap_or_default(),
+        lon.to_string(),
+        lat.to_string(),
+        extent,
+        properties
+            .score
+            .map(|score| score.to_string())
+            .unwrap_or_default(),
+        match_type.to_string(),
+    ];
+
+    for column in extra_columns {
+        let value = properties
+            .extra
+            .as_ref()
+            .and_then(|extra| extra.get(column))
+            .cloned()
+            .unwrap_or_default();
+        record.push(value);
+    }
+
+    return record;
+}
+
+fn render_ndjson(response: &PhotonResponse) -> Vec<u8> {
+    let mut body: Vec<u8> = vec![];
+    for feature in &response.features {
+        serde_json::to_writer(&mut body, feature).unwrap();
+        body.push(b'\n');
+    }
+    return body;
+}
@@ -0,0 +1,439 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::address_format::format_label;
+use crate::country_codes::{alpha2_country_code, is_alpha2_country_code};
+use crate::errors::ValidationError;
+use crate::query::{Envelope, HOUSE_WITHOUT_NUMBER_LAYER, HOUSE_WITH_NUMBER_LAYER};
+use crate::response::{osm_type_name, PhotonGeometry, PhotonResponse, PhotonResponseProperties};
+use crate::validation::validate_bbox;
+
+const PELIAS_VERSION: &str = "0.2";
+const ATTRIBUTION: &str = "Data © OpenStreetMap contributors";
+
+const PELIAS_LAYERS: [(&str, &[&str]); 14] = [
+    ("venue", &[HOUSE_WITHOUT_NUMBER_LAYER]),
+    ("address", &[HOUSE_WITH_NUMBER_LAYER]),
+    ("street", &["street"]),
+    ("neighbourhood", &["locality"]),
+    ("borough", &["district"]),
+    ("locality", &["city"]),
+    ("localadmin", &["city"]),
+    ("county", &["county"]),
+    ("macrocounty", &["county"]),
+    ("region", &["state"]),
+    ("macroregion", &["state"]),
+    ("country", &["country"]),
+    ("postalcode", &["city", "district", "locality"]),
+    (
+        "coarse",
+        &["locality", "district", "city", "county", "state", "country"],
+    ),
+];
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PeliasSearchRequest {
+    /// Free text query, must not be empty
+    pub text: String,
+    /// Latitude of the location bias, requires `focus.point.lon`
+    #[serde(rename = "focus.point.lat")]
+    #[param(minimum = -90, maximum = 90)]
+    pub focus_point_lat: Option<f32>,
+    /// Longitude of the location bias, requires `focus.point.lat`
+    #[serde(rename = "focus.point.lon")]
+    #[param(minimum = -180, maximum = 180)]
+    pub focus_point_lon: Option<f32>,
+    /// Bounding box edge, all four `boundary.rect` parameters are required together
+    #[serde(rename = "boundary.rect.min_lat")]
+    pub boundary_rect_min_lat: Option<f32>,
+    #[serde(rename = "boundary.rect.min_lon")]
+    pub boundary_rect_min_lon: Option<f32>,
+    #[serde(rename = "boundary.rect.max_lat")]
+    pub boundary_rect_max_lat: Option<f32>,
+    #[serde(rename = "boundary.rect.max_lon")]
+    pub boundary_rect_max_lon: Option<f32>,
+    /// Comma separated ISO 3166-1 alpha-2 or alpha-3 country codes
+    #[serde(rename = "boundary.country")]
+    pub boundary_country: Option<String>,
+    /// Comma separated Pelias layers like venue, address, street, locality or country
+    pub layers: Option<String>,
+    /// Maximum number of results, at most 40 for search and 10 for autocomplete
+    pub size: Option<i64>,
+    /// Result language, one of the configured languages
+    pub lang: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PeliasReverseRequest {
+    #[serde(rename = "point.lat")]
+    #[param(minimum = -90, maximum = 90)]
+    pub point_lat: f32,
+    #[serde(rename = "point.lon")]
+    #[param(minimum = -180, maximum = 180)]
+    pub point_lon: f32,
+    /// Search radius in kilometers, defaults to 1
+    #[serde(rename = "boundary.circle.radius")]
+    pub boundary_circle_radius: Option<u64>,
+    /// Comma separated ISO 3166-1 alpha-2 or alpha-3 country codes
+    #[serde(rename = "boundary.country")]
+    pub boundary_country: Option<String>,
+    /// Comma separated Pelias layers like venue, address, street, locality or country
+    pub layers: Option<String>,
+    /// Maximum number of results, at most 40
+    pub size: Option<i64>,
+    /// Result language, one of the configured languages
+    pub lang: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PeliasResponse {
+    pub geocoding: PeliasHeader,
+    pub r#type: String,
+    pub features: Vec<PeliasFeature>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PeliasHeader {
+    pub version: String,
+    pub attribution: String,
+    #[schema(value_type = Object)]
+    pub query: Value,
+    pub timestamp: u128,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PeliasFeature {
+    pub r#type: String,
+    pub geometry: PhotonGeometry,
+    pub properties: PeliasProperties,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bbox: Option<[f32; 4]>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PeliasProperties {
+    pub id: String,
+    pub gid: String,
+    pub layer: String,
+    pub source: String,
+    pub source_id: String,
+    pub name: Option<String>,
+    pub housenumber: Option<String>,
+    pub street: Option<String>,
+    pub postalcode: Option<String>,
+    pub country: Option<String>,
+    pub country_code: Option<String>,
+    pub region: Option<String>,
+    pub county: Option<String>,
+    pub locality: Option<String>,
+    pub borough: Option<String>,
+    pub neighbourhood: Option<String>,
+    pub label: Option<String>,
+}
+
+pub fn parse_layers(layers: &Option<String>) -> Result<Option<HashSet<String>>, ValidationError> {
+    let layers = match layers {
+        Some(layers) => layers,
+        None => return Ok(None),
+    };
+
+    let mut address_types = HashSet::new();
+    for layer in layers
+        .split(',')
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+    {
+        match PELIAS_LAYERS.iter().find(|(name, _)| *name == layer) {
+            Some((_, types)) => address_types.extend(types.iter().map(|t| t.to_string())),
+            None => {
+                return Err(ValidationError::Layer {
+                    value: layer.to_string(),
+                    valid: PELIAS_LAYERS
+                        .iter()
+                        .map(|(name, _)| name.to_string())
+                        .collect(),
+                })
+            }
+        }
+    }
+
+    if address_types.is_empty() {
+        return Ok(None);
+    }
+
+    return Ok(Some(address_types));
+}
+
+pub fn validate_text(text: &str) -> Result<(), ValidationError> {
+    if text.trim().is_empty() {
+        return Err(ValidationError::EmptyText);
+    }
+    return Ok(());
+}
+
+pub fn parse_boundary_rect(
+    request: &PeliasSearchRequest,
+) -> Result<Option<Envelope>, ValidationError> {
+    return match (
+        request.boundary_rect_min_lon,
+        request.boundary_rect_min_lat,
+        request.boundary_rect_max_lon,
+        request.boundary_rect_max_lat,
+    ) {
+        (Some(min_lon), Some(min_lat), Some(max_lon), Some(max_lat)) => {
+            validate_bbox(&Some([min_lon, min_lat, max_lon, max_lat]))
+        }
+        (None, None, None, None) => Ok(None),
+        _ => Err(ValidationError::BoundaryRect),
+    };
+}
+
+// Pelias accepts both alpha-2 and alpha-3 codes, Photon only stores alpha-2 codes
+pub fn parse_boundary_country(
+    countries: &Option<String>,
+) -> Result<Option<HashSet<String>>, ValidationError> {
+    let countries = match countries {
+        Some(countries) => countries,
+        None => return Ok(None),
+    };
+
+    let mut codes = HashSet::new();
+    for country in countries
+        .split(',')
+        .map(|c| c.trim())
+        .filter(|c| !c.is_empty())
+    {
+        let code = match country.len() {
+            2 if is_alpha2_country_code(country) => country.to_uppercase(),
+            3 => match alpha2_country_code(country) {
+                Some(code) => code.to_string(),
+                None => return Err(ValidationError::Country(country.to_string())),
+            },
+            _ => return Err(ValidationError::Country(country.to_string())),
+        };
+        codes.insert(code);
+    }
+
+    if codes.is_empty() {
+        return Ok(None);
+    }
+
+    return Ok(Some(codes));
+}
+
+pub fn to_pelias(response: PhotonResponse, query: Value) -> PeliasResponse {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+
+    return PeliasResponse {
+        geocoding: PeliasHeader {
+            version: PELIAS_VERSION.to_string(),
+            attribution: ATTRIBUTION.to_string(),
+            query,
+            timestamp,
+        },
+        r#type: "FeatureCollection".to_string(),
+        features: response
+            .features
+            .into_iter()
+            .map(|feature| feature_to_pelias(feature.properties))
+            .collect(),
+    };
+}
+
+fn feature_to_pelias(properties: PhotonResponseProperties) -> PeliasFeature {
+    let layer = pelias_layer(&properties);
+    let source_id = format!(
+        "{}/{}",
+        osm_type_name(&properties.osm_type),
+        properties.osm_id
+    );
+
+    return PeliasFeature {
+        r#type: "Feature".to_string(),
+        bbox: properties
+            .extent
+            .map(|[min_lon, max_lat, max_lon, min_lat]| [min_lon, min_lat, max_lon, max_lat]),
+        properties: PeliasProperties {
+            id: source_id.clone(),
+            gid: format!("openstreetmap:{}:{}", layer, source_id),
+            layer: layer.to_string(),
+            source: "openstreetmap".to_string(),
+            source_id,
+            label: format_label(&properties),
+            name: properties.name,
+            housenumber: properties.housenumber,
+            street: properties.street,
+            postalcode: properties.postcode,
+            country: properties.country,
+            country_code: properties.countrycode,
+            region: properties.state,
+            county: properties.county,
+            locality: properties.city,
+            borough: properties.district,
+            neighbourhood: properties.locality,
+        },
+        geometry: properties.geometry,
+    };
+}
+
+fn pelias_layer(properties: &PhotonResponseProperties) -> &'static str {
+    return match properties.r#type.as_str() {
+        "house" if properties.housenumber.is_none() => "venue",
+        "house" => "address",
+        "street" => "street",
+        "locality" => "neighbourhood",
+        "district" => "borough",
+        "city" => "locality",
+        "county" => "county",
+        "state" => "region",
+        "country" => "country",
+        _ => "venue",
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(
+        min_lon: Option<f32>,
+        min_lat: Option<f32>,
+        max_lon: Option<f32>,
+        max_lat: Option<f32>,
+    ) -> PeliasSearchRequest {
+        return PeliasSearchRequest {
+            text: "berlin".to_string(),
+            focus_point_lat: None,
+            focus_point_lon: None,
+            boundary_rect_min_lat: min_lat,
+            boundary_rect_min_lon: min_lon,
+            boundary_rect_max_lat: max_lat,
+            boundary_rect_max_lon: max_lon,
+            boundary_country: None,
+            layers: None,
+            size: None,
+            lang: None,
+        };
+    }
+
+    fn set(values: &[&str]) -> HashSet<String> {
+        return values.iter().map(|value| value.to_string()).collect();
+    }
+
+    #[test]
+    fn venue_and_address_are_distinct_layers() {
+        assert_eq!(
+            parse_layers(&Some("venue".to_string())).unwrap(),
+            Some(set(&[HOUSE_WITHOUT_NUMBER_LAYER]))
+        );
+        assert_eq!(
+            parse_layers(&Some("address, street".to_string())).unwrap(),
+            Some(set(&[HOUSE_WITH_NUMBER_LAYER, "street"]))
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_layers() {
+        assert!(parse_layers(&Some("venue,planet".to_string())).is_err());
+        assert_eq!(parse_layers(&Some(" , ".to_string())).unwrap(), None);
+        assert_eq!(parse_layers(&None).unwrap(), None);
+    }
+
+    #[test]
+    fn parses_boundary_rect() {
+        let envelope =
+            parse_boundary_rect(&request(Some(13.0), Some(52.3), Some(13.8), Some(52.6)))
+                .unwrap()
+                .unwrap();
+
+        assert_eq!(envelope.min_lon, 13.0);
+        assert_eq!(envelope.min_lat, 52.3);
+        assert_eq!(envelope.max_lon, 13.8);
+        assert_eq!(envelope.max_lat, 52.6);
+        assert!(parse_boundary_rect(&request(None, None, None, None))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn rejects_partial_boundary_rect() {
+        assert!(matches!(
+            parse_boundary_rect(&request(Some(13.0), Some(52.3), None, None)),
+            Err(ValidationError::BoundaryRect)
+        ));
+        assert!(matches!(
+            parse_boundary_rect(&request(None, None, None, Some(52.6))),
+            Err(ValidationError::BoundaryRect)
+        ));
+    }
+
+    #[test]
+    fn converts_alpha3_boundary_country() {
+        assert_eq!(
+            parse_boundary_country(&Some("USA,de, CAN".to_string())).unwrap(),
+            Some(set(&["US", "DE", "CA"]))
+        );
+        assert_eq!(parse_boundary_country(&None).unwrap(), None);
+        assert_eq!(
+            parse_boundary_country(&Some(",".to_string())).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_empty_text() {
+        assert!(validate_text("berlin").is_ok());
+        assert!(matches!(validate_text(""), Err(ValidationError::EmptyText)));
+        assert!(matches!(
+            validate_text("  "),
+            Err(ValidationError::EmptyText)
+        ));
+    }
+
+    #[test]
+    fn rejects_unknown_boundary_country() {
+        for country in ["XYZ", "XX", "Germany", "D"] {
+            assert!(matches!(
+                parse_boundary_country(&Some(country.to_string())),
+                Err(ValidationError::Country(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn house_layer_depends_on_housenumber() {
+        let venue = PhotonResponseProperties {
+            r#type: "house".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(pelias_layer(&venue), "venue");
+
+        let address = PhotonResponseProperties {
+            housenumber: Some("12".to_string()),
+            ..venue
+        };
+        assert_eq!(pelias_layer(&address), "address");
+        assert_eq!(pelias_layer(&PhotonResponseProperties::default()), "venue");
+    }
+
+    #[test]
+    fn feature_ids_use_osm_type_names() {
+        let feature = feature_to_pelias(PhotonResponseProperties {
+            osm_type: "W".to_string(),
+            osm_id: 42,
+            r#type: "street".to_string(),
+            ..Default::default()
+        });
+
+        assert_eq!(feature.properties.source_id, "way/42");
+        assert_eq!(feature.properties.gid, "openstreetmap:street:way/42");
+    }
+}
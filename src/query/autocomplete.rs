@@ -0,0 +1,78 @@
+use elasticsearch_dsl::{
+    Decay, DecayFunction, MultiMatchQuery, Query, Search, Suggester, TextQueryType,
+};
+
+use crate::query::filters::{add_query_filters, add_top_level_filters, QueryFilters};
+use crate::query::location_bias::{add_location_bias, LocationBias};
+use crate::query::named::{FULL_NAME_QUERY, PREFIX_QUERY};
+use crate::query::profile::RankingProfile;
+use crate::query::search::build_top_level_filter_query;
+
+pub const AUTOCOMPLETE_SUGGESTER: &str = "autocomplete";
+
+pub fn build_autocomplete_query(
+    q: &str,
+    language: &str,
+    languages: &[String],
+    filters: &QueryFilters,
+    location_bias: &Option<LocationBias>,
+    profile: &RankingProfile,
+) -> Search {
+    let prefix_query = build_prefix_query(q, language, languages, profile);
+    let full_name_query = Query::r#match(format!("name.{}.raw", language), q).name(FULL_NAME_QUERY);
+
+    let mut scored = Query::function_score()
+        .query(
+            Query::bool()
+                .must(prefix_query)
+                .should(full_name_query.boost(profile.name_ngram_boost)),
+        )
+        .function(Decay::new(
+            DecayFunction::Linear,
+            "importance",
+            1.0,
+            profile.importance_scale,
+        ));
+    scored = add_location_bias(scored, location_bias, profile);
+
+    let mut top_level_filter = build_top_level_filter_query(q, language);
+    top_level_filter = add_top_level_filters(filters, location_bias, top_level_filter);
+
+    let mut final_query = Query::bool().must(scored);
+    final_query = add_query_filters(filters, final_query);
+    final_query = final_query.filter(top_level_filter);
+
+    return Search::new().query(final_query);
+}
+
+pub fn build_autocomplete_suggest_query(q: &String, field: &String, size: &i64) -> Search {
+    return Search::new().size(0).suggest(
+        AUTOCOMPLETE_SUGGESTER,
+        Suggester::completion(field, q)
+            .size(*size as u64)
+            .skip_duplicates(true),
+    );
+}
+
+fn build_prefix_query(
+    q: &str,
+    language: &str,
+    languages: &[String],
+    profile: &RankingProfile,
+) -> MultiMatchQuery {
+    let mut fields: Vec<String> = vec!["collector.default^1.0".into()];
+
+    for lang in languages {
+        let boost = if lang == language {
+            1.0
+        } else {
+            profile.other_language_boost
+        };
+        fields.push(format!("collector.{}^{}", lang, boost));
+    }
+
+    return Query::multi_match(fields, q)
+        .r#type(TextQueryType::BoolPrefix)
+        .minimum_should_match("100%")
+        .name(PREFIX_QUERY);
+}
@@ -19,9 +19,9 @@ fn build_bbox_query(bbox: &Envelope) -> GeoBoundingBoxQuery {
     return Query::geo_bounding_box(
         "coordinate",
         GeoBoundingBox::Vertices {
-            top: bbox.min_lat,
+            top: bbox.max_lat,
             left: bbox.min_lon,
-            bottom: bbox.max_lat,
+            bottom: bbox.min_lat,
             right: bbox.max_lon,
         },
     );
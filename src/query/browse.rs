@@ -0,0 +1,30 @@
+use elasticsearch_dsl::{Decay, DecayFunction, Query, Search};
+
+use crate::query::filters::{add_query_filters, add_top_level_filters, QueryFilters};
+use crate::query::location_bias::{add_location_bias, LocationBias};
+use crate::query::profile::RankingProfile;
+
+pub fn build_browse_query(
+    filters: &QueryFilters,
+    location_bias: &Option<LocationBias>,
+    profile: &RankingProfile,
+) -> Search {
+    let mut scored = Query::function_score()
+        .query(Query::match_all())
+        .function(Decay::new(
+            DecayFunction::Linear,
+            "importance",
+            1.0,
+            profile.importance_scale,
+        ));
+    scored = add_location_bias(scored, location_bias, profile);
+
+    let mut top_level_filter = Query::bool();
+    top_level_filter = add_top_level_filters(filters, location_bias, top_level_filter);
+
+    let mut final_query = Query::bool().must(scored);
+    final_query = add_query_filters(filters, final_query);
+    final_query = final_query.filter(top_level_filter);
+
+    return Search::new().query(final_query);
+}
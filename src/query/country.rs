@@ -0,0 +1,15 @@
+use elasticsearch_dsl::{BoolQuery, Query, TermsQuery};
+use std::collections::HashSet;
+
+pub fn add_country_filter(countries: &Option<HashSet<String>>, query: BoolQuery) -> BoolQuery {
+    if let Some(countries) = countries {
+        let country_query = build_country_filter_query(countries);
+        return query.filter(country_query);
+    }
+    return query;
+}
+
+fn build_country_filter_query(countries: &HashSet<String>) -> TermsQuery {
+    let countries: Vec<String> = countries.iter().map(|c| c.to_uppercase()).collect();
+    return Query::terms("countrycode", countries);
+}
@@ -1,15 +1,23 @@
 use elasticsearch_dsl::{Fuzziness, MultiMatchQuery, Query, TextQueryType};
 
+use crate::query::named::FIELDS_QUERY;
+use crate::query::profile::RankingProfile;
+
 pub fn build_fields_query(
-    q: &String,
-    language: &String,
-    languages: &Vec<String>,
+    q: &str,
+    language: &str,
+    languages: &[String],
     lenient: &bool,
+    profile: &RankingProfile,
 ) -> MultiMatchQuery {
     let mut fields: Vec<String> = vec!["collector.default^1.0".into()];
 
     for lang in languages {
-        let boost = if lang == language { 1.0 } else { 0.6 };
+        let boost = if lang == language {
+            1.0
+        } else {
+            profile.other_language_boost
+        };
         fields.push(format!("collector.{}.ngrams^{}", lang, boost));
     }
 
@@ -19,12 +27,13 @@ pub fn build_fields_query(
         TextQueryType::CrossFields
     };
 
-    let mut fields_query = Query::multi_match(fields, q.clone())
+    let mut fields_query = Query::multi_match(fields, q)
         .r#type(text_query_type)
         .prefix_length(2)
         .analyzer("search_ngram")
-        .tie_breaker(0.4)
-        .minimum_should_match(if *lenient { "-34%" } else { "100%" });
+        .tie_breaker(profile.fields_tie_breaker)
+        .minimum_should_match(if *lenient { "-34%" } else { "100%" })
+        .name(FIELDS_QUERY);
 
     if *lenient {
         fields_query = fields_query.fuzziness(Fuzziness::Auto);
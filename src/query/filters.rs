@@ -0,0 +1,47 @@
+use elasticsearch_dsl::BoolQuery;
+use std::collections::HashSet;
+
+use crate::query::bbox::{add_bounding_box_filter, Envelope};
+use crate::query::country::add_country_filter;
+use crate::query::layer::add_layer_filter;
+use crate::query::location_bias::{add_radius_filter, LocationBias};
+use crate::query::osm_tag::add_osm_tag_filter;
+
+// Restrictions on which places a search, autocomplete or browse query may return
+#[derive(Default)]
+pub struct QueryFilters {
+    pub osm_tags: Option<HashSet<String>>,
+    pub envelope: Option<Envelope>,
+    pub layers: Option<HashSet<String>>,
+    pub countries: Option<HashSet<String>>,
+    // Kilometers around the location bias point, ignored without a location bias
+    pub radius: Option<u64>,
+}
+
+impl QueryFilters {
+    pub fn is_empty(&self) -> bool {
+        return self.osm_tags.is_none()
+            && self.envelope.is_none()
+            && self.layers.is_none()
+            && self.countries.is_none()
+            && self.radius.is_none();
+    }
+}
+
+// Tag and country filters apply to the whole query, the others to the top level filter
+pub fn add_query_filters(filters: &QueryFilters, query: BoolQuery) -> BoolQuery {
+    let mut query = add_osm_tag_filter(&filters.osm_tags, query);
+    query = add_country_filter(&filters.countries, query);
+    return query;
+}
+
+pub fn add_top_level_filters(
+    filters: &QueryFilters,
+    location_bias: &Option<LocationBias>,
+    query: BoolQuery,
+) -> BoolQuery {
+    let mut query = add_bounding_box_filter(&filters.envelope, query);
+    query = add_layer_filter(&filters.layers, query);
+    query = add_radius_filter(&filters.radius, location_bias, query);
+    return query;
+}
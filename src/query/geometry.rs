@@ -0,0 +1,21 @@
+use elasticsearch_dsl::{Search, SourceFilter};
+use serde_json::{json, Value};
+
+pub const GEOMETRY_FIELD: &str = "geometry";
+
+// Geometries can be large, they are only fetched when a query asks for them
+pub fn add_geometry_source(search: Search, full_geometry: &bool) -> Search {
+    if *full_geometry {
+        return search.source(SourceFilter::Enable(true));
+    }
+    return search;
+}
+
+pub fn exclude_geometry_by_default(search: Search) -> Value {
+    let mut body = json!(search);
+    if let Some(body) = body.as_object_mut() {
+        body.entry("_source")
+            .or_insert_with(|| json!({ "excludes": [GEOMETRY_FIELD] }));
+    }
+    return body;
+}
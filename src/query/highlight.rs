@@ -0,0 +1,22 @@
+use elasticsearch_dsl::{Encoder, Highlight, Highlighter, Search};
+
+pub fn add_highlight(search: Search, language: &String, highlight: &bool) -> Search {
+    if *highlight {
+        return search.highlight(build_highlight(language));
+    }
+    return search;
+}
+
+fn build_highlight(language: &String) -> Highlight {
+    return Highlight::new()
+        .highlighter(
+            Highlighter::new()
+                .tags((["<b>"], ["</b>"]))
+                // Escape the names so only our tags end up as markup in the client
+                .encoder(Encoder::Html)
+                .require_field_match(false)
+                .number_of_fragments(0),
+        )
+        .field(format!("name.{}", language))
+        .field(format!("collector.{}", language));
+}
@@ -1,6 +1,11 @@
-use elasticsearch_dsl::{BoolQuery, Query, TermsQuery};
+use elasticsearch_dsl::{BoolQuery, Query};
 use std::collections::HashSet;
 
+pub const HOUSE_LAYER: &str = "house";
+// Subsets of the house layer, split by whether the place has a house number
+pub const HOUSE_WITH_NUMBER_LAYER: &str = "house:housenumber";
+pub const HOUSE_WITHOUT_NUMBER_LAYER: &str = "house:name";
+
 pub fn add_layer_filter(layers: &Option<HashSet<String>>, query: BoolQuery) -> BoolQuery {
     if let Some(layers) = layers {
         let layer_query = build_layer_filter_query(layers);
@@ -9,6 +14,103 @@ pub fn add_layer_filter(layers: &Option<HashSet<String>>, query: BoolQuery) -> B
     return query;
 }
 
-pub fn build_layer_filter_query(filters: &HashSet<String>) -> TermsQuery {
-    return Query::terms("type", filters);
+pub fn build_layer_filter_query(filters: &HashSet<String>) -> BoolQuery {
+    let with_number = filters.contains(HOUSE_WITH_NUMBER_LAYER);
+    let without_number = filters.contains(HOUSE_WITHOUT_NUMBER_LAYER);
+
+    let mut types: HashSet<&str> = filters
+        .iter()
+        .map(|layer| layer.as_str())
+        .filter(|layer| *layer != HOUSE_WITH_NUMBER_LAYER && *layer != HOUSE_WITHOUT_NUMBER_LAYER)
+        .collect();
+    if with_number && without_number {
+        types.insert(HOUSE_LAYER);
+    }
+
+    let all_houses = types.contains(HOUSE_LAYER);
+
+    let mut query = Query::bool().minimum_should_match(1);
+    if !types.is_empty() {
+        query = query.should(Query::terms(
+            "type",
+            types.into_iter().collect::<Vec<&str>>(),
+        ));
+    }
+    if all_houses {
+        return query;
+    }
+
+    let house = Query::bool().filter(Query::term("type", HOUSE_LAYER));
+    if with_number {
+        query = query.should(house.filter(Query::exists("housenumber")));
+    } else if without_number {
+        query = query.should(house.must_not(Query::exists("housenumber")));
+    }
+
+    return query;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn filter(layers: &[&str]) -> Value {
+        let layers: HashSet<String> = layers.iter().map(|layer| layer.to_string()).collect();
+        return serde_json::to_value(build_layer_filter_query(&layers)).unwrap();
+    }
+
+    #[test]
+    fn filters_types() {
+        assert_eq!(
+            filter(&["street"]),
+            json!({"bool": {
+                "minimum_should_match": "1",
+                "should": [{"terms": {"type": ["street"]}}]
+            }})
+        );
+    }
+
+    #[test]
+    fn houses_with_number() {
+        assert_eq!(
+            filter(&[HOUSE_WITH_NUMBER_LAYER]),
+            json!({"bool": {
+                "minimum_should_match": "1",
+                "should": [{"bool": {"filter": [
+                    {"term": {"type": {"value": "house"}}},
+                    {"exists": {"field": "housenumber"}}
+                ]}}]
+            }})
+        );
+    }
+
+    #[test]
+    fn houses_without_number_or_streets() {
+        assert_eq!(
+            filter(&[HOUSE_WITHOUT_NUMBER_LAYER, "street"]),
+            json!({"bool": {
+                "minimum_should_match": "1",
+                "should": [
+                    {"terms": {"type": ["street"]}},
+                    {"bool": {
+                        "filter": [{"term": {"type": {"value": "house"}}}],
+                        "must_not": [{"exists": {"field": "housenumber"}}]
+                    }}
+                ]
+            }})
+        );
+    }
+
+    #[test]
+    fn both_house_subsets_are_all_houses() {
+        assert_eq!(
+            filter(&[HOUSE_WITH_NUMBER_LAYER, HOUSE_WITHOUT_NUMBER_LAYER]),
+            filter(&[HOUSE_LAYER])
+        );
+        assert_eq!(
+            filter(&[HOUSE_LAYER, HOUSE_WITH_NUMBER_LAYER]),
+            filter(&[HOUSE_LAYER])
+        );
+    }
 }
@@ -1,8 +1,10 @@
 use elasticsearch_dsl::{
-    Decay, DecayFunction, Distance, Function, FunctionBoostMode, FunctionScoreMode,
+    BoolQuery, Decay, DecayFunction, Distance, Function, FunctionBoostMode, FunctionScoreMode,
     FunctionScoreQuery, GeoLocation, Query,
 };
 
+use crate::query::profile::RankingProfile;
+
 pub struct Point {
     pub x: f32,
     pub y: f32,
@@ -17,22 +19,40 @@ pub struct LocationBias {
 pub fn add_location_bias(
     query: FunctionScoreQuery,
     bias: &Option<LocationBias>,
+    profile: &RankingProfile,
 ) -> FunctionScoreQuery {
     if let Some(bias) = bias {
         if bias.zoom < 4 {
             return query;
         }
-        let location_bias_query = build_location_bias_query(bias);
+        let location_bias_query = build_location_bias_query(bias, profile);
         return location_bias_query.query(query);
     }
     return query;
 }
 
-fn build_location_bias_query(bias: &LocationBias) -> FunctionScoreQuery {
+// Only keeps places within `radius` kilometers of the location bias point
+pub fn add_radius_filter(
+    radius: &Option<u64>,
+    bias: &Option<LocationBias>,
+    query: BoolQuery,
+) -> BoolQuery {
+    if let (Some(radius), Some(bias)) = (radius, bias) {
+        return query.filter(Query::geo_distance(
+            "coordinate",
+            GeoLocation::new(bias.point.y, bias.point.x),
+            Distance::Kilometers(*radius),
+        ));
+    }
+    return query;
+}
+
+fn build_location_bias_query(bias: &LocationBias, profile: &RankingProfile) -> FunctionScoreQuery {
     const MIN_SCALE: f64 = 0.0000001;
     const MAX_ZOOM: i64 = 18;
 
-    let radius = ((1 << (18 - std::cmp::min(bias.zoom, MAX_ZOOM))) / 4) as u64;
+    let radius = (1 << (18 - std::cmp::min(bias.zoom, MAX_ZOOM))) as u64
+        / profile.location_bias_radius_divisor;
 
     let scale = if bias.scale < MIN_SCALE {
         MIN_SCALE
@@ -48,8 +68,10 @@ fn build_location_bias_query(bias: &LocationBias) -> FunctionScoreQuery {
                 GeoLocation::new(bias.point.x, bias.point.y),
                 Distance::Kilometers(radius),
             )
-            .offset(Distance::Kilometers(radius / 10))
-            .decay(0.8),
+            .offset(Distance::Kilometers(
+                radius / profile.location_bias_offset_divisor,
+            ))
+            .decay(profile.location_bias_decay),
         )
         .function(Decay::new(DecayFunction::Linear, "importance", 1.0, scale))
         .boost_mode(FunctionBoostMode::Multiply)
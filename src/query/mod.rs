@@ -1,13 +1,36 @@
+mod autocomplete;
 mod bbox;
+mod browse;
+mod country;
 mod fields;
+mod filters;
+mod geometry;
+mod highlight;
 mod layer;
 mod location_bias;
 mod name_ngram;
+mod named;
 mod osm_tag;
+mod profile;
 mod reverse;
 mod search;
+mod similar;
 
+pub use autocomplete::{
+    build_autocomplete_query, build_autocomplete_suggest_query, AUTOCOMPLETE_SUGGESTER,
+};
 pub use bbox::Envelope;
+pub use browse::build_browse_query;
+pub use filters::QueryFilters;
+pub use geometry::{add_geometry_source, exclude_geometry_by_default, GEOMETRY_FIELD};
+pub use highlight::add_highlight;
+pub use layer::{HOUSE_LAYER, HOUSE_WITHOUT_NUMBER_LAYER, HOUSE_WITH_NUMBER_LAYER};
 pub use location_bias::{LocationBias, Point};
+pub use named::{
+    CLASSIFICATION_QUERY, COLLECTOR_RAW_QUERY, FIELDS_QUERY, FULL_NAME_QUERY, HOUSENUMBER_QUERY,
+    NAME_NGRAM_QUERY, PREFIX_QUERY,
+};
+pub use profile::RankingProfile;
 pub use reverse::build_reverse_query;
 pub use search::build_search_query;
+pub use similar::build_similar_query;
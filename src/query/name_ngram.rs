@@ -1,10 +1,14 @@
 use elasticsearch_dsl::{MultiMatchQuery, Query, TextQueryType};
 
+use crate::query::named::NAME_NGRAM_QUERY;
+use crate::query::profile::RankingProfile;
+
 pub fn build_name_ngram_query(
-    q: &String,
-    language: &String,
-    languages: &Vec<String>,
+    q: &str,
+    language: &str,
+    languages: &[String],
     lenient: &bool,
+    profile: &RankingProfile,
 ) -> MultiMatchQuery {
     let default_language = if "default" == language {
         &languages[0]
@@ -16,16 +20,21 @@ pub fn build_name_ngram_query(
     let mut fields: Vec<String> = vec![];
 
     for lang in languages {
-        let boost = if lang == default_language { 1.0 } else { 0.4 };
+        let boost = if lang == default_language {
+            1.0
+        } else {
+            profile.name_other_language_boost
+        };
         fields.push(format!("name.{}.ngrams^{}", lang, boost));
     }
 
     for alt in alt_names {
-        fields.push(format!("name.{}.raw^{}", alt, 0.4));
+        fields.push(format!("name.{}.raw^{}", alt, profile.alt_name_boost));
     }
 
-    return Query::multi_match(fields, q.clone())
+    return Query::multi_match(fields, q)
         .r#type(TextQueryType::BestFields)
         .fuzziness(if *lenient { 1 } else { 0 })
-        .analyzer("search_ngram");
+        .analyzer("search_ngram")
+        .name(NAME_NGRAM_QUERY);
 }
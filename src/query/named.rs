@@ -0,0 +1,7 @@
+pub const FULL_NAME_QUERY: &str = "name.raw";
+pub const NAME_NGRAM_QUERY: &str = "name.ngrams";
+pub const FIELDS_QUERY: &str = "collector.ngrams";
+pub const COLLECTOR_RAW_QUERY: &str = "collector.raw";
+pub const PREFIX_QUERY: &str = "collector.prefix";
+pub const HOUSENUMBER_QUERY: &str = "housenumber";
+pub const CLASSIFICATION_QUERY: &str = "classification";
@@ -36,8 +36,8 @@ fn build_osm_tag_filter_query(filter_strings: &HashSet<String>) -> Option<BoolQu
         if filter.filter_type == OsmTagFilterType::ExcludeValue {
             exclude_query = exclude_query.should(
                 Query::bool()
-                    .must(Query::term("osm_key", &filter.key.unwrap()))
-                    .must_not(Query::term("osm_value", &filter.value.unwrap())),
+                    .must(Query::term("osm_key", filter.key.unwrap()))
+                    .must_not(Query::term("osm_value", filter.value.unwrap())),
             );
             continue;
         }
@@ -78,13 +78,13 @@ fn build_osm_tag_filter_query(filter_strings: &HashSet<String>) -> Option<BoolQu
 
     let mut tag_filter_query = Query::bool();
 
-    tag_filter_query = if &include_query != &Query::bool() {
+    tag_filter_query = if include_query != Query::bool() {
         tag_filter_query.must(include_query)
     } else {
         tag_filter_query
     };
 
-    tag_filter_query = if &exclude_query != &Query::bool() {
+    tag_filter_query = if exclude_query != Query::bool() {
         tag_filter_query.must_not(exclude_query)
     } else {
         tag_filter_query
@@ -0,0 +1,37 @@
+use serde::Deserialize;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct RankingProfile {
+    pub other_language_boost: f32,
+    pub fields_tie_breaker: f32,
+    pub name_ngram_boost: f32,
+    pub name_other_language_boost: f32,
+    pub alt_name_boost: f32,
+    pub housenumber_weight: f32,
+    pub housenumber_boost: f32,
+    pub classification_weight: f32,
+    pub importance_scale: f64,
+    pub location_bias_decay: f32,
+    pub location_bias_radius_divisor: u64,
+    pub location_bias_offset_divisor: u64,
+}
+
+impl Default for RankingProfile {
+    fn default() -> Self {
+        return RankingProfile {
+            other_language_boost: 0.6,
+            fields_tie_breaker: 0.4,
+            name_ngram_boost: 2.0,
+            name_other_language_boost: 0.4,
+            alt_name_boost: 0.4,
+            housenumber_weight: 10.0,
+            housenumber_boost: 0.3,
+            classification_weight: 0.1,
+            importance_scale: 0.6,
+            location_bias_decay: 0.8,
+            location_bias_radius_divisor: 4,
+            location_bias_offset_divisor: 10,
+        };
+    }
+}
@@ -1,20 +1,22 @@
+use crate::query::country::add_country_filter;
 use crate::query::layer::build_layer_filter_query;
+use crate::query::location_bias::Point;
 use crate::query::osm_tag::add_osm_tag_filter;
 use elasticsearch_dsl::{Distance, GeoDistanceSort, GeoLocation, Query, Search, SortOrder};
 use std::collections::HashSet;
 
 pub fn build_reverse_query(
-    lat: &f32,
-    lon: &f32,
+    point: &Point,
     radius: &u64,
     query_string_filter: &Option<String>,
     distance_sort: &bool,
     layers: &Option<HashSet<String>>,
     filters: &Option<HashSet<String>>,
+    countries: &Option<HashSet<String>>,
 ) -> Search {
     let geo_distance_query = Query::geo_distance(
         "coordinate",
-        GeoLocation::new(*lat, *lon),
+        GeoLocation::new(point.y, point.x),
         Distance::Kilometers(*radius),
     );
 
@@ -41,6 +43,7 @@ pub fn build_reverse_query(
     };
 
     query = add_osm_tag_filter(filters, query);
+    query = add_country_filter(countries, query);
 
     query = if match_all {
         query.must(Query::match_all())
@@ -52,12 +55,14 @@ pub fn build_reverse_query(
 
     let mut search = Search::new().query(query);
     search = if *distance_sort {
-        search.sort(
-            GeoDistanceSort::new("coordinate", GeoLocation::new(*lat, *lon)).order(SortOrder::Asc),
-        )
+        search.sort(build_distance_sort(&point.y, &point.x))
     } else {
         search
     };
 
     return search;
 }
+
+pub fn build_distance_sort(lat: &f32, lon: &f32) -> GeoDistanceSort {
+    return GeoDistanceSort::new("coordinate", GeoLocation::new(*lat, *lon)).order(SortOrder::Asc);
+}
@@ -2,47 +2,47 @@ use elasticsearch_dsl::{
     BoolQuery, Decay, DecayFunction, Function, FunctionScoreMode, FunctionScoreQuery, Fuzziness,
     MatchQuery, MultiMatchQuery, Query, Search, TextQueryType, Weight,
 };
-use std::collections::HashSet;
 
-use crate::query::bbox::{add_bounding_box_filter, Envelope};
 use crate::query::fields::build_fields_query;
-use crate::query::layer::add_layer_filter;
+use crate::query::filters::{add_query_filters, add_top_level_filters, QueryFilters};
 use crate::query::location_bias::{add_location_bias, LocationBias};
 use crate::query::name_ngram::build_name_ngram_query;
-use crate::query::osm_tag::add_osm_tag_filter;
+use crate::query::named::{
+    CLASSIFICATION_QUERY, COLLECTOR_RAW_QUERY, FULL_NAME_QUERY, HOUSENUMBER_QUERY,
+};
+use crate::query::profile::RankingProfile;
 
 pub fn build_search_query(
-    q: &String,
-    language: &String,
-    languages: &Vec<String>,
+    q: &str,
+    language: &str,
+    languages: &[String],
     lenient: &bool,
-    filters: &Option<HashSet<String>>,
-    bbox: &Option<Envelope>,
-    layers: &Option<HashSet<String>>,
+    filters: &QueryFilters,
     location_bias: &Option<LocationBias>,
+    profile: &RankingProfile,
 ) -> Search {
-    let mut unfiltered = build_unfiltered_query(&q, &language, &languages, &lenient);
-    unfiltered = add_location_bias(unfiltered, location_bias);
+    let mut unfiltered = build_unfiltered_query(q, language, languages, lenient, profile);
+    unfiltered = add_location_bias(unfiltered, location_bias, profile);
 
-    let mut top_level_filter = build_top_level_filter_query(&q, &language);
-    top_level_filter = add_bounding_box_filter(bbox, top_level_filter);
-    top_level_filter = add_layer_filter(layers, top_level_filter);
+    let mut top_level_filter = build_top_level_filter_query(q, language);
+    top_level_filter = add_top_level_filters(filters, location_bias, top_level_filter);
 
     let mut final_query = Query::bool().must(unfiltered);
-    final_query = add_osm_tag_filter(filters, final_query);
+    final_query = add_query_filters(filters, final_query);
     final_query = final_query.filter(top_level_filter);
 
     return Search::new().query(final_query);
 }
 
 fn build_unfiltered_query(
-    q: &String,
-    language: &String,
-    languages: &Vec<String>,
+    q: &str,
+    language: &str,
+    languages: &[String],
     lenient: &bool,
+    profile: &RankingProfile,
 ) -> FunctionScoreQuery {
-    let fields_query = build_fields_query(q, language, languages, lenient);
-    let function_score_query = build_function_score_query(q, language, languages);
+    let fields_query = build_fields_query(q, language, languages, lenient, profile);
+    let function_score_query = build_function_score_query(q, language, languages, profile);
     let full_name_query = build_full_name_query(q, language, lenient);
 
     let mut query = Query::bool();
@@ -50,28 +50,43 @@ fn build_unfiltered_query(
     query = query.should(function_score_query);
     query = query.should(full_name_query);
 
-    let name_ngram_query = build_name_ngram_query(q, language, languages, lenient);
+    let name_ngram_query = build_name_ngram_query(q, language, languages, lenient, profile);
 
     query = if !(q.contains(",") || q.contains(" ")) {
-        query.must(name_ngram_query.boost(2))
+        query.must(name_ngram_query.boost(profile.name_ngram_boost))
     } else {
         query.must(
             Query::bool()
                 .should(name_ngram_query)
-                .should(Query::r#match("housenumber", q.clone()).analyzer("standard"))
-                .should(Query::r#match("classification", q.clone()).boost(0.1))
+                .should(
+                    Query::r#match("housenumber", q)
+                        .analyzer("standard")
+                        .name(HOUSENUMBER_QUERY),
+                )
+                .should(
+                    Query::r#match("classification", q)
+                        .boost(0.1)
+                        .name(CLASSIFICATION_QUERY),
+                )
                 .minimum_should_match(1),
         )
     };
 
     return Query::function_score()
         .query(query)
-        .function(Decay::new(DecayFunction::Linear, "importance", 1.0, 0.6))
-        .function(Weight::new(0.1).filter(Query::r#match("classification", q.clone())))
+        .function(Decay::new(
+            DecayFunction::Linear,
+            "importance",
+            1.0,
+            profile.importance_scale,
+        ))
+        .function(
+            Weight::new(profile.classification_weight).filter(Query::r#match("classification", q)),
+        )
         .score_mode(FunctionScoreMode::Sum);
 }
 
-fn build_top_level_filter_query(q: &str, language: &str) -> BoolQuery {
+pub fn build_top_level_filter_query(q: &str, language: &str) -> BoolQuery {
     return Query::bool()
         .should(Query::bool().must_not(Query::exists("housenumber")))
         .should(Query::r#match("housenumber", q).analyzer("standard"))
@@ -79,39 +94,49 @@ fn build_top_level_filter_query(q: &str, language: &str) -> BoolQuery {
 }
 
 fn build_house_number_query(
-    q: &String,
-    language: &String,
-    languages: &Vec<String>,
+    q: &str,
+    language: &str,
+    languages: &[String],
+    profile: &RankingProfile,
 ) -> MultiMatchQuery {
     let mut fields: Vec<String> = vec!["collector.default.raw^1.0".into()];
 
     for lang in languages {
-        let boost = if lang == language { 1.0 } else { 0.6 };
+        let boost = if lang == language {
+            1.0
+        } else {
+            profile.other_language_boost
+        };
         fields.push(format!("collector.{}.raw^{}", lang, boost));
     }
 
-    return Query::multi_match(fields, q.clone()).r#type(TextQueryType::BestFields);
+    return Query::multi_match(fields, q)
+        .r#type(TextQueryType::BestFields)
+        .name(COLLECTOR_RAW_QUERY);
 }
 
 fn build_function_score_query(
-    q: &String,
-    language: &String,
-    languages: &Vec<String>,
+    q: &str,
+    language: &str,
+    languages: &[String],
+    profile: &RankingProfile,
 ) -> FunctionScoreQuery {
-    let filter_query = Query::r#match("housenumber", q.clone()).analyzer("standard");
+    let filter_query = Query::r#match("housenumber", q).analyzer("standard");
 
-    let weight_function = Function::weight(10f32).filter(filter_query);
+    let weight_function = Function::weight(profile.housenumber_weight).filter(filter_query);
 
     return Query::function_score()
-        .query(build_house_number_query(q, language, languages))
-        .boost(0.3)
+        .query(build_house_number_query(q, language, languages, profile))
+        .boost(profile.housenumber_boost)
         .function(weight_function);
 }
 
 fn build_full_name_query(q: &str, language: &str, lenient: &bool) -> MatchQuery {
-    return Query::r#match(format!("name.{}.raw", language), q).fuzziness(if *lenient {
-        Fuzziness::Auto
-    } else {
-        Fuzziness::Distance(0)
-    });
+    return Query::r#match(format!("name.{}.raw", language), q)
+        .fuzziness(if *lenient {
+            Fuzziness::Auto
+        } else {
+            Fuzziness::Distance(0)
+        })
+        .name(FULL_NAME_QUERY);
 }
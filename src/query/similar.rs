@@ -0,0 +1,29 @@
+use crate::query::reverse::build_distance_sort;
+use elasticsearch_dsl::{Distance, GeoLocation, Query, Search};
+
+pub fn build_similar_query(
+    place_id: &str,
+    osm_key: &str,
+    osm_value: &str,
+    lat: &f32,
+    lon: &f32,
+    radius: &u64,
+) -> Search {
+    let geo_distance_query = Query::geo_distance(
+        "coordinate",
+        GeoLocation::new(*lat, *lon),
+        Distance::Kilometers(*radius),
+    );
+
+    // Tag values are taken verbatim, they may contain characters the osm_tag syntax treats specially
+    let query = Query::bool()
+        .must(Query::match_all())
+        .filter(Query::term("osm_key", osm_key))
+        .filter(Query::term("osm_value", osm_value))
+        .must_not(Query::ids([place_id]))
+        .filter(geo_distance_query);
+
+    return Search::new()
+        .query(query)
+        .sort(build_distance_sort(lat, lon));
+}
@@ -1,37 +1,184 @@
 use serde::Deserialize;
 use std::collections::HashSet;
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct PhotonSearchRequest {
-    pub q: String,
+    /// Free text query, required unless `osm_tag` or `layer` is given
+    pub q: Option<String>,
+    /// Result language, one of the configured languages
     pub lang: Option<String>,
+    /// Longitude of the location bias, requires `lat`
+    #[param(minimum = -180, maximum = 180)]
     pub lon: Option<f32>,
+    /// Latitude of the location bias, requires `lon`
+    #[param(minimum = -90, maximum = 90)]
     pub lat: Option<f32>,
+    /// Maximum number of results, at most 10 with `autocomplete=true`
     pub limit: Option<i64>,
+    /// Weight of the location bias against importance
+    #[param(minimum = 0, maximum = 1)]
     pub location_bias_scale: Option<f64>,
+    /// Only return results within this many kilometers of `lon`/`lat`
+    pub radius: Option<u64>,
+    /// Bounding box as `min_lon,min_lat,max_lon,max_lat`
+    #[param(value_type = Option<Vec<f32>>, min_items = 4, max_items = 4)]
     pub bbox: Option<[f32; 4]>,
+    /// Map zoom level used to scale the location bias
+    #[param(maximum = 18)]
     pub zoom: Option<i64>,
+    /// OSM tag filters like `key:value`, `key`, `:value` or negated with `!`
+    #[param(value_type = Option<Vec<String>>)]
     pub osm_tag: Option<HashSet<String>>,
+    /// One of house, street, locality, district, city, county, state, country
+    #[param(value_type = Option<Vec<String>>)]
     pub layer: Option<HashSet<String>>,
+    /// Ranking profile name, defaults to `default`
+    pub profile: Option<String>,
+    /// Use prefix matching tuned for search-as-you-type
+    pub autocomplete: Option<bool>,
+    /// Return matched name and address fragments wrapped in `<b>` tags, the rest of the text is HTML escaped
+    pub highlight: Option<bool>,
+    /// Output format, defaults to the `Accept` header and then `geojson`
+    pub format: Option<OutputFormat>,
+    /// Comma separated `extra` keys added as `extra.<key>` columns to `format=csv`
+    pub extra_columns: Option<String>,
+    /// Return the stored polygon or line geometry as `full_geometry`
+    pub geometry: Option<GeometryDetail>,
+    /// Simplification tolerance for `geometry=full`, in degrees
+    #[param(minimum = 0)]
+    pub geometry_tolerance: Option<f64>,
+    /// Report documents that were skipped because they could not be read
     pub debug: Option<bool>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct PhotonReverseRequest {
+    /// Result language, one of the configured languages
     pub lang: Option<String>,
+    #[param(minimum = -180, maximum = 180)]
     pub lon: f32,
+    #[param(minimum = -90, maximum = 90)]
     pub lat: f32,
+    /// Search radius in kilometers
     pub radius: u64,
+    /// Elasticsearch query string the results must match
     pub query_string_filter: Option<String>,
+    /// Sort by distance instead of relevance, defaults to true
     pub distance_sort: Option<bool>,
+    /// Maximum number of results
     pub limit: Option<i64>,
+    /// OSM tag filters like `key:value`, `key`, `:value` or negated with `!`
+    #[param(value_type = Option<Vec<String>>)]
     pub osm_tag: Option<HashSet<String>>,
+    /// One of house, street, locality, district, city, county, state, country
+    #[param(value_type = Option<Vec<String>>)]
     pub layer: Option<HashSet<String>>,
+    /// Output format, defaults to the `Accept` header and then `geojson`
+    pub format: Option<OutputFormat>,
+    /// Comma separated `extra` keys added as `extra.<key>` columns to `format=csv`
+    pub extra_columns: Option<String>,
+    /// Return the stored polygon or line geometry as `full_geometry`
+    pub geometry: Option<GeometryDetail>,
+    /// Simplification tolerance for `geometry=full`, in degrees
+    #[param(minimum = 0)]
+    pub geometry_tolerance: Option<f64>,
+    /// Report documents that were skipped because they could not be read
     pub debug: Option<bool>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct PhotonLookupRequest {
     pub place_id: String,
+    /// Result language, one of the configured languages
+    pub lang: Option<String>,
+    /// Output format, defaults to the `Accept` header and then `geojson`
+    pub format: Option<OutputFormat>,
+    /// Comma separated `extra` keys added as `extra.<key>` columns to `format=csv`
+    pub extra_columns: Option<String>,
+    /// Return the stored polygon or line geometry as `full_geometry`
+    pub geometry: Option<GeometryDetail>,
+    /// Simplification tolerance for `geometry=full`, in degrees
+    #[param(minimum = 0)]
+    pub geometry_tolerance: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PhotonSimilarRequest {
+    /// Place to find similar places for
+    pub place_id: String,
+    /// Result language, one of the configured languages
+    pub lang: Option<String>,
+    /// Search radius in kilometers around the place, defaults to 1
+    #[param(minimum = 1, maximum = 100)]
+    pub radius: Option<u64>,
+    /// Maximum number of results, defaults to 10
+    #[param(minimum = 1, maximum = 50)]
+    pub limit: Option<i64>,
+    /// Output format, defaults to the `Accept` header and then `geojson`
+    pub format: Option<OutputFormat>,
+    /// Comma separated `extra` keys added as `extra.<key>` columns to `format=csv`
+    pub extra_columns: Option<String>,
+    /// Return the stored polygon or line geometry as `full_geometry`
+    pub geometry: Option<GeometryDetail>,
+    /// Simplification tolerance for `geometry=full`, in degrees
+    #[param(minimum = 0)]
+    pub geometry_tolerance: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PhotonTileRequest {
+    /// Language of the `name` tag, one of the configured languages
     pub lang: Option<String>,
 }
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PhotonSessionRequest {
+    /// Milliseconds to wait for further keystrokes before searching
+    #[param(maximum = 1000)]
+    pub debounce: Option<u64>,
+    /// API key, alternatively sent in the x-api-key header
+    pub api_key: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PhotonAliasSwapRequest {
+    /// Index the alias should point to
+    pub index: String,
+    /// Allowed relative drop in document count compared to the current index, defaults to 0.1
+    #[param(minimum = 0, maximum = 1)]
+    pub max_count_drop: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PhotonSessionMessage {
+    pub seq: u64,
+    #[serde(flatten)]
+    pub request: PhotonSearchRequest,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    GeoJson,
+    GeocodeJson,
+    Label,
+    Csv,
+    NdJson,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum GeometryDetail {
+    #[default]
+    Point,
+    Full,
+}
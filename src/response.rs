@@ -1,19 +1,69 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct PhotonResponse {
     pub r#type: String,
     pub features: Vec<PhotonResponseFeature>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub lenient: bool,
+    #[serde(skip)]
+    pub skipped: Vec<SkippedDocument>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub debug: Option<PhotonResponseDebug>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PhotonResponseDebug {
+    pub skipped_documents: Vec<SkippedDocument>,
+}
+
+// A hit whose `_source` could not be read as a PhotonDocument
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SkippedDocument {
+    pub id: String,
+    pub error: String,
+}
+
+impl PhotonResponse {
+    pub fn into_lenient(mut self) -> Self {
+        self.lenient = true;
+        for feature in &mut self.features {
+            feature.properties.match_type = match feature.properties.match_type {
+                Some(MatchType::Exact) => Some(MatchType::Fuzzy),
+                _ => Some(MatchType::Lenient),
+            };
+        }
+        return self;
+    }
+
+    pub fn with_debug(mut self, debug: &bool) -> Self {
+        if *debug {
+            self.debug = Some(PhotonResponseDebug {
+                skipped_documents: self.skipped.clone(),
+            });
+        }
+        return self;
+    }
 }
 
 #[derive(Debug, Serialize)]
+pub struct PhotonSessionResponse {
+    pub seq: Option<u64>,
+    #[serde(flatten)]
+    pub response: Option<PhotonResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct PhotonResponseFeature {
     pub r#type: String,
     pub properties: PhotonResponseProperties,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Default, Serialize, ToSchema)]
 pub struct PhotonResponseProperties {
     pub parent_place_id: Option<i64>,
     pub place_id: i64,
@@ -33,14 +83,93 @@ pub struct PhotonResponseProperties {
     pub street: Option<String>,
     pub state: Option<String>,
     pub county: Option<String>,
+    pub formatted: Option<String>,
     pub extent: Option<[f32; 4]>,
     pub extra: Option<HashMap<String, String>>,
     pub names: Option<HashMap<String, String>>,
     pub geometry: PhotonGeometry,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub full_geometry: Option<FullGeometry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matched_fields: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub match_type: Option<MatchType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlight: Option<HashMap<String, Vec<String>>>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchType {
+    /// The whole name matched
+    Exact,
+    /// The house number matched
+    Housenumber,
+    /// Every term matched a whole word of the name or address
+    Address,
+    /// The name matched partially
+    Name,
+    /// The last term matched as a prefix while typing
+    Prefix,
+    /// The name or address matched partially
+    Partial,
+    /// Only the place's classification matched
+    Classification,
+    /// Lenient search matched the whole name despite typos
+    Fuzzy,
+    /// Only lenient search matched
+    Lenient,
+}
+
+impl MatchType {
+    pub fn as_str(&self) -> &'static str {
+        return match self {
+            MatchType::Exact => "exact",
+            MatchType::Housenumber => "housenumber",
+            MatchType::Address => "address",
+            MatchType::Name => "name",
+            MatchType::Prefix => "prefix",
+            MatchType::Partial => "partial",
+            MatchType::Classification => "classification",
+            MatchType::Fuzzy => "fuzzy",
+            MatchType::Lenient => "lenient",
+        };
+    }
+}
+
+#[derive(Debug, Default, Serialize, ToSchema)]
 pub struct PhotonGeometry {
     pub r#type: String,
     pub coordinates: [f32; 2],
 }
+
+pub type Position = [f64; 2];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", content = "coordinates")]
+pub enum FullGeometry {
+    #[serde(alias = "point")]
+    Point([f64; 2]),
+    #[serde(alias = "multipoint")]
+    MultiPoint(Vec<[f64; 2]>),
+    #[serde(alias = "linestring")]
+    LineString(Vec<[f64; 2]>),
+    #[serde(alias = "multilinestring")]
+    MultiLineString(Vec<Vec<[f64; 2]>>),
+    #[serde(alias = "polygon")]
+    Polygon(Vec<Vec<[f64; 2]>>),
+    #[serde(alias = "multipolygon")]
+    MultiPolygon(Vec<Vec<Vec<[f64; 2]>>>),
+}
+
+// Photon stores the single letter OSM type, the Nominatim and Pelias formats spell it out
+pub fn osm_type_name(osm_type: &str) -> &str {
+    return match osm_type {
+        "N" => "node",
+        "W" => "way",
+        "R" => "relation",
+        other => other,
+    };
+}
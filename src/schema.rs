@@ -0,0 +1,83 @@
+use elasticsearch::Elasticsearch;
+
+use crate::elastic::{fetch_database_version, fetch_field_mappings};
+use crate::errors::SchemaError;
+
+// Photon importers store their version in a document next to the places
+pub const DATABASE_PROPERTIES_ID: &str = "DATABASE_PROPERTIES";
+pub const DATABASE_VERSION_FIELD: &str = "database_version";
+
+pub fn required_fields(languages: &Vec<String>) -> Vec<String> {
+    let mut fields: Vec<String> = vec![
+        "importance".into(),
+        "coordinate".into(),
+        "collector.default".into(),
+        "collector.default.raw".into(),
+    ];
+
+    for lang in languages {
+        fields.push(format!("collector.{}", lang));
+        fields.push(format!("collector.{}.ngrams", lang));
+        fields.push(format!("collector.{}.raw", lang));
+        fields.push(format!("name.{}.ngrams", lang));
+        fields.push(format!("name.{}.raw", lang));
+    }
+
+    return fields;
+}
+
+pub async fn check_index_schema(
+    client: &Elasticsearch,
+    index: &str,
+    languages: &Vec<String>,
+    supported_versions: &Option<Vec<String>>,
+) -> Result<Option<String>, SchemaError> {
+    let version = fetch_database_version(client, index).await?;
+
+    if let Some(supported) = supported_versions {
+        match &version {
+            Some(value) if !supported.contains(value) => {
+                return Err(SchemaError::Version {
+                    value: value.clone(),
+                    supported: supported.clone(),
+                });
+            }
+            Some(_) => {}
+            None => return Err(SchemaError::MissingVersion),
+        }
+    }
+
+    let required = required_fields(languages);
+    let fields: Vec<&str> = required.iter().map(|field| field.as_str()).collect();
+
+    let mut mappings: Vec<(String, Vec<String>)> = fetch_field_mappings(client, index, &fields)
+        .await?
+        .into_iter()
+        .collect();
+    mappings.sort();
+
+    // Unknown fields are left out of the response, an index without any of them has no entry
+    if mappings.is_empty() {
+        return Err(SchemaError::MissingFields {
+            index: index.to_string(),
+            fields: required,
+        });
+    }
+
+    for (index, existing) in mappings {
+        let missing: Vec<String> = required
+            .iter()
+            .filter(|field| !existing.contains(field))
+            .cloned()
+            .collect();
+
+        if !missing.is_empty() {
+            return Err(SchemaError::MissingFields {
+                index,
+                fields: missing,
+            });
+        }
+    }
+
+    return Ok(version);
+}
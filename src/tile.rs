@@ -0,0 +1,346 @@
+use axum::http::header::CONTENT_TYPE;
+use axum::response::{IntoResponse, Response};
+use prost::Message;
+use std::collections::{HashMap, HashSet};
+use std::f64::consts::PI;
+
+use crate::errors::ValidationError;
+use crate::query::Envelope;
+use crate::response::{PhotonResponse, PhotonResponseProperties};
+use crate::vector_tile::{Feature, GeomType, Layer, Tile, Value};
+
+pub const MAX_TILE_ZOOM: u32 = 22;
+pub const TILE_FEATURE_LIMIT: i64 = 500;
+
+const MVT_CONTENT_TYPE: &str = "application/vnd.mapbox-vector-tile";
+const MVT_VERSION: u32 = 2;
+const MVT_EXTENT: u32 = 4096;
+const MVT_LAYER_NAME: &str = "places";
+const MVT_EXTENSION: &str = ".mvt";
+const MAX_MERCATOR_LAT: f64 = 85.051_128_779_806_59;
+
+pub struct TileCoordinate {
+    pub z: u32,
+    pub x: u32,
+    pub y: u32,
+}
+
+pub fn parse_tile_coordinate(z: u32, x: u32, y: &str) -> Result<TileCoordinate, ValidationError> {
+    let invalid = || ValidationError::Tile(format!("{}/{}/{}", z, x, y));
+
+    let y = y
+        .strip_suffix(MVT_EXTENSION)
+        .and_then(|y| y.parse::<u32>().ok())
+        .ok_or_else(invalid)?;
+
+    let tiles = 1u64 << z.min(MAX_TILE_ZOOM);
+    if z > MAX_TILE_ZOOM || x as u64 >= tiles || y as u64 >= tiles {
+        return Err(invalid());
+    }
+
+    return Ok(TileCoordinate { z, x, y });
+}
+
+pub fn tile_envelope(tile: &TileCoordinate) -> Envelope {
+    return Envelope {
+        min_lon: tile_lon(tile.x, tile.z) as f32,
+        min_lat: tile_lat(tile.y + 1, tile.z) as f32,
+        max_lon: tile_lon(tile.x + 1, tile.z) as f32,
+        max_lat: tile_lat(tile.y, tile.z) as f32,
+    };
+}
+
+pub fn layers_for_tile_zoom(zoom: &u32) -> HashSet<String> {
+    let layers: &[&str] = match zoom {
+        0..=3 => &["country"],
+        4..=5 => &["country", "state"],
+        6..=8 => &["country", "state", "county"],
+        9..=10 => &["country", "state", "county", "city"],
+        11..=14 => &["state", "county", "city", "district", "locality"],
+        15..=16 => &["city", "district", "locality", "street"],
+        _ => &["district", "locality", "street", "house"],
+    };
+
+    return layers.iter().map(|layer| layer.to_string()).collect();
+}
+
+pub fn render_tile(response: &PhotonResponse, tile: &TileCoordinate) -> Response {
+    let body = encode_tile(response, tile).encode_to_vec();
+    return ([(CONTENT_TYPE, MVT_CONTENT_TYPE)], body).into_response();
+}
+
+fn encode_tile(response: &PhotonResponse, tile: &TileCoordinate) -> Tile {
+    let mut layer = LayerBuilder::default();
+
+    for feature in &response.features {
+        let properties = &feature.properties;
+        let [lon, lat] = properties.geometry.coordinates;
+        let (x, y) = project(lon as f64, lat as f64, tile);
+        if !(0..=MVT_EXTENT as i64).contains(&x) || !(0..=MVT_EXTENT as i64).contains(&y) {
+            continue;
+        }
+
+        layer.add_feature(properties, x, y);
+    }
+
+    return Tile {
+        layers: vec![layer.build()],
+    };
+}
+
+#[derive(Default)]
+struct LayerBuilder {
+    features: Vec<Feature>,
+    keys: Vec<String>,
+    key_index: HashMap<String, u32>,
+    values: Vec<Value>,
+    value_index: HashMap<String, u32>,
+}
+
+impl LayerBuilder {
+    fn add_feature(&mut self, properties: &PhotonResponseProperties, x: i64, y: i64) {
+        let mut tags = vec![];
+        let mut tag = |builder: &mut Self, key: &str, value: Option<String>| {
+            if let Some(value) = value {
+                tags.push(builder.key(key));
+                tags.push(builder.value(value));
+            }
+        };
+
+        tag(self, "name", properties.name.clone());
+        tag(self, "osm_key", Some(properties.osm_key.clone()));
+        tag(self, "osm_value", Some(properties.osm_value.clone()));
+        tag(self, "osm_type", Some(properties.osm_type.clone()));
+        tag(self, "osm_id", Some(properties.osm_id.to_string()));
+        tag(self, "type", Some(properties.r#type.clone()));
+
+        self.features.push(Feature {
+            id: Some(properties.place_id as u64),
+            tags,
+            r#type: Some(GeomType::Point as i32),
+            // a single MoveTo command followed by the zigzag encoded point
+            geometry: vec![command(1, 1), zigzag(x), zigzag(y)],
+        });
+    }
+
+    fn key(&mut self, key: &str) -> u32 {
+        if let Some(index) = self.key_index.get(key) {
+            return *index;
+        }
+        let index = self.keys.len() as u32;
+        self.keys.push(key.to_string());
+        self.key_index.insert(key.to_string(), index);
+        return index;
+    }
+
+    fn value(&mut self, value: String) -> u32 {
+        if let Some(index) = self.value_index.get(&value) {
+            return *index;
+        }
+        let index = self.values.len() as u32;
+        self.values.push(Value {
+            string_value: Some(value.clone()),
+            ..Default::default()
+        });
+        self.value_index.insert(value, index);
+        return index;
+    }
+
+    fn build(self) -> Layer {
+        return Layer {
+            version: MVT_VERSION,
+            name: MVT_LAYER_NAME.to_string(),
+            features: self.features,
+            keys: self.keys,
+            values: self.values,
+            extent: Some(MVT_EXTENT),
+        };
+    }
+}
+
+fn project(lon: f64, lat: f64, tile: &TileCoordinate) -> (i64, i64) {
+    let scale = (1u64 << tile.z) as f64;
+    let lat = lat.clamp(-MAX_MERCATOR_LAT, MAX_MERCATOR_LAT).to_radians();
+
+    let world_x = (lon + 180.0) / 360.0 * scale;
+    let world_y = (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / PI) / 2.0 * scale;
+
+    let x = ((world_x - tile.x as f64) * MVT_EXTENT as f64).round() as i64;
+    let y = ((world_y - tile.y as f64) * MVT_EXTENT as f64).round() as i64;
+    return (x, y);
+}
+
+fn tile_lon(x: u32, z: u32) -> f64 {
+    return x as f64 / (1u64 << z) as f64 * 360.0 - 180.0;
+}
+
+fn tile_lat(y: u32, z: u32) -> f64 {
+    let n = PI - 2.0 * PI * y as f64 / (1u64 << z) as f64;
+    return n.sinh().atan().to_degrees();
+}
+
+fn command(id: u32, count: u32) -> u32 {
+    return (id & 0x7) | (count << 3);
+}
+
+fn zigzag(value: i64) -> u32 {
+    return ((value << 1) ^ (value >> 63)) as u32;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::{PhotonGeometry, PhotonResponseFeature};
+
+    fn feature(place_id: i64, name: &str, lon: f32, lat: f32) -> PhotonResponseFeature {
+        return PhotonResponseFeature {
+            r#type: "Feature".to_string(),
+            properties: PhotonResponseProperties {
+                place_id,
+                osm_type: "N".to_string(),
+                osm_id: place_id,
+                osm_key: "place".to_string(),
+                osm_value: "city".to_string(),
+                r#type: "city".to_string(),
+                name: Some(name.to_string()),
+                geometry: PhotonGeometry {
+                    r#type: "Point".to_string(),
+                    coordinates: [lon, lat],
+                },
+                ..Default::default()
+            },
+        };
+    }
+
+    fn response(features: Vec<PhotonResponseFeature>) -> PhotonResponse {
+        return PhotonResponse {
+            r#type: "FeatureCollection".to_string(),
+            features,
+            lenient: false,
+            skipped: vec![],
+            debug: None,
+        };
+    }
+
+    #[test]
+    fn parses_tile_coordinates() {
+        let tile = parse_tile_coordinate(3, 4, "5.mvt").unwrap();
+
+        assert_eq!((tile.z, tile.x, tile.y), (3, 4, 5));
+    }
+
+    #[test]
+    fn rejects_invalid_tile_coordinates() {
+        assert!(parse_tile_coordinate(3, 4, "5").is_err());
+        assert!(parse_tile_coordinate(3, 4, "5.png").is_err());
+        assert!(parse_tile_coordinate(3, 4, "-1.mvt").is_err());
+        assert!(parse_tile_coordinate(3, 8, "0.mvt").is_err());
+        assert!(parse_tile_coordinate(3, 0, "8.mvt").is_err());
+        assert!(parse_tile_coordinate(MAX_TILE_ZOOM + 1, 0, "0.mvt").is_err());
+        assert!(parse_tile_coordinate(MAX_TILE_ZOOM, 0, "0.mvt").is_ok());
+    }
+
+    #[test]
+    fn envelope_of_world_tile() {
+        let envelope = tile_envelope(&TileCoordinate { z: 0, x: 0, y: 0 });
+
+        assert_eq!(envelope.min_lon, -180.0);
+        assert_eq!(envelope.max_lon, 180.0);
+        assert!((envelope.max_lat - MAX_MERCATOR_LAT as f32).abs() < 1e-4);
+        assert!((envelope.min_lat + MAX_MERCATOR_LAT as f32).abs() < 1e-4);
+    }
+
+    #[test]
+    fn envelope_of_north_east_tile() {
+        let envelope = tile_envelope(&TileCoordinate { z: 1, x: 1, y: 0 });
+
+        assert_eq!(envelope.min_lon, 0.0);
+        assert_eq!(envelope.max_lon, 180.0);
+        assert!(envelope.min_lat.abs() < 1e-4);
+        assert!((envelope.max_lat - MAX_MERCATOR_LAT as f32).abs() < 1e-4);
+    }
+
+    #[test]
+    fn projects_into_tile_extent() {
+        let world = TileCoordinate { z: 0, x: 0, y: 0 };
+
+        assert_eq!(project(0.0, 0.0, &world), (2048, 2048));
+        assert_eq!(project(-180.0, MAX_MERCATOR_LAT, &world), (0, 0));
+        assert_eq!(project(180.0, -90.0, &world), (4096, 4096));
+        assert_eq!(
+            project(0.0, 0.0, &TileCoordinate { z: 1, x: 1, y: 1 }),
+            (0, 0)
+        );
+    }
+
+    #[test]
+    fn encodes_commands_and_zigzag() {
+        assert_eq!(command(1, 1), 9);
+        assert_eq!(command(2, 3), 26);
+        assert_eq!(zigzag(0), 0);
+        assert_eq!(zigzag(-1), 1);
+        assert_eq!(zigzag(1), 2);
+        assert_eq!(zigzag(-2), 3);
+        assert_eq!(zigzag(2048), 4096);
+    }
+
+    #[test]
+    fn encodes_features_inside_the_tile() {
+        let tile = TileCoordinate { z: 1, x: 1, y: 0 };
+        let response = response(vec![
+            feature(1, "Berlin", 13.4, 52.5),
+            feature(2, "Hamburg", 10.0, 53.55),
+            feature(3, "Buenos Aires", -58.4, -34.6),
+        ]);
+
+        let encoded = encode_tile(&response, &tile).encode_to_vec();
+        let decoded = Tile::decode(encoded.as_slice()).unwrap();
+        let layer = &decoded.layers[0];
+
+        assert_eq!(layer.name, MVT_LAYER_NAME);
+        assert_eq!(layer.version, MVT_VERSION);
+        assert_eq!(layer.extent, Some(MVT_EXTENT));
+        assert_eq!(layer.features.len(), 2);
+
+        let berlin = &layer.features[0];
+        let (x, y) = project(13.4, 52.5, &tile);
+        assert_eq!(berlin.id, Some(1));
+        assert_eq!(berlin.r#type, Some(GeomType::Point as i32));
+        assert_eq!(berlin.geometry, vec![command(1, 1), zigzag(x), zigzag(y)]);
+    }
+
+    #[test]
+    fn shares_keys_and_values_between_features() {
+        let tile = TileCoordinate { z: 0, x: 0, y: 0 };
+        let layer = &encode_tile(
+            &response(vec![
+                feature(1, "Berlin", 13.4, 52.5),
+                feature(2, "Hamburg", 10.0, 53.55),
+            ]),
+            &tile,
+        )
+        .layers[0];
+
+        let tags = |feature: &Feature| -> Vec<(String, String)> {
+            return feature
+                .tags
+                .chunks(2)
+                .map(|tag| {
+                    (
+                        layer.keys[tag[0] as usize].clone(),
+                        layer.values[tag[1] as usize].string_value.clone().unwrap(),
+                    )
+                })
+                .collect();
+        };
+
+        assert_eq!(
+            layer.keys,
+            vec!["name", "osm_key", "osm_value", "osm_type", "osm_id", "type"]
+        );
+        // "city" is stored once for osm_value and type of both features
+        assert_eq!(layer.values.len(), 7);
+        assert!(tags(&layer.features[1]).contains(&("name".to_string(), "Hamburg".to_string())));
+        assert!(tags(&layer.features[1]).contains(&("osm_id".to_string(), "2".to_string())));
+    }
+}
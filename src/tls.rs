@@ -0,0 +1,197 @@
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use axum::extract::{ConnectInfo, Request};
+use axum::Router;
+use hyper::body::Incoming;
+use hyper::service::service_fn;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto::Builder;
+use rustls::pki_types::CertificateDer;
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::{CertifiedKey, SigningKey};
+use rustls::{ServerConfig, SignatureScheme};
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+use tower_service::Service;
+
+use crate::config::TlsConfig;
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug)]
+pub struct CertificateResolver {
+    cert_path: String,
+    key_path: String,
+    current: RwLock<Arc<CertifiedKey>>,
+}
+
+impl CertificateResolver {
+    pub fn new(config: &TlsConfig) -> io::Result<Self> {
+        let certified_key = load_certified_key(&config.cert_path, &config.key_path)?;
+
+        return Ok(CertificateResolver {
+            cert_path: config.cert_path.clone(),
+            key_path: config.key_path.clone(),
+            current: RwLock::new(Arc::new(certified_key)),
+        });
+    }
+
+    fn reload(&self) -> io::Result<()> {
+        let certified_key = load_certified_key(&self.cert_path, &self.key_path)?;
+        *self.current.write().unwrap() = Arc::new(certified_key);
+        return Ok(());
+    }
+
+    fn modified(&self) -> Option<(SystemTime, SystemTime)> {
+        let cert = std::fs::metadata(&self.cert_path).and_then(|meta| meta.modified());
+        let key = std::fs::metadata(&self.key_path).and_then(|meta| meta.modified());
+        return cert.ok().zip(key.ok());
+    }
+}
+
+impl ResolvesServerCert for CertificateResolver {
+    fn resolve(&self, _client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        return Some(self.current.read().unwrap().clone());
+    }
+}
+
+pub fn tls_acceptor(resolver: Arc<CertificateResolver>) -> TlsAcceptor {
+    let mut config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_cert_resolver(resolver);
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+    return TlsAcceptor::from(Arc::new(config));
+}
+
+// Polls the modification times, so it also works for mounted secrets that are swapped via symlinks
+pub fn watch_certificates(resolver: Arc<CertificateResolver>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut last_modified = resolver.modified();
+        loop {
+            tokio::time::sleep(interval).await;
+
+            let modified = resolver.modified();
+            if modified == last_modified {
+                continue;
+            }
+
+            match resolver.reload() {
+                Ok(()) => {
+                    println!("Reloaded TLS certificate from {}", resolver.cert_path);
+                    last_modified = modified;
+                }
+                // Keep serving the previous certificate, cert and key may be written one after the other
+                Err(err) => println!("Failed to reload TLS certificate: {}", err),
+            }
+        }
+    });
+}
+
+pub async fn serve_tls(listener: TcpListener, acceptor: TlsAcceptor, router: Router) {
+    loop {
+        let (stream, remote_address) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(err) => {
+                println!("Failed to accept connection: {}", err);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+
+        let acceptor = acceptor.clone();
+        let router = router.clone();
+
+        tokio::spawn(async move {
+            // Clients that never finish the handshake would otherwise hold the task forever
+            let stream =
+                match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                    Ok(Ok(stream)) => stream,
+                    _ => return,
+                };
+
+            let service = service_fn(move |mut request: Request<Incoming>| {
+                request
+                    .extensions_mut()
+                    .insert(ConnectInfo::<SocketAddr>(remote_address));
+                return router.clone().call(request);
+            });
+
+            let _ = Builder::new(TokioExecutor::new())
+                .serve_connection_with_upgrades(TokioIo::new(stream), service)
+                .await;
+        });
+    }
+}
+
+fn load_certified_key(cert_path: &str, key_path: &str) -> io::Result<CertifiedKey> {
+    let certs = rustls_pemfile::certs(&mut BufReader::new(File::open(cert_path)?))
+        .collect::<io::Result<Vec<_>>>()?;
+    if certs.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("no certificates found in {}", cert_path),
+        ));
+    }
+
+    let key = match rustls_pemfile::private_key(&mut BufReader::new(File::open(key_path)?))? {
+        Some(key) => key,
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("no private key found in {}", key_path),
+            ))
+        }
+    };
+    let signing_key = rustls::crypto::ring::sign::any_supported_type(&key)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+    // Cert and key are often written one after the other, never swap in a mismatched pair
+    if !key_matches_certificate(&certs[0], signing_key.as_ref()) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "private key in {} does not match the certificate in {}",
+                key_path, cert_path
+            ),
+        ));
+    }
+
+    return Ok(CertifiedKey::new(certs, signing_key));
+}
+
+// Signs a message with the key and verifies it with the certificate's public key
+fn key_matches_certificate(cert: &CertificateDer, signing_key: &dyn SigningKey) -> bool {
+    const MESSAGE: &[u8] = b"photon certificate check";
+    let mapping = rustls::crypto::ring::default_provider()
+        .signature_verification_algorithms
+        .mapping;
+
+    let schemes: Vec<SignatureScheme> = mapping.iter().map(|(scheme, _)| *scheme).collect();
+    let signer = match signing_key.choose_scheme(&schemes) {
+        Some(signer) => signer,
+        None => return false,
+    };
+    let signature = match signer.sign(MESSAGE) {
+        Ok(signature) => signature,
+        Err(_) => return false,
+    };
+    let end_entity = match webpki::EndEntityCert::try_from(cert) {
+        Ok(end_entity) => end_entity,
+        Err(_) => return false,
+    };
+
+    return mapping
+        .iter()
+        .filter(|(scheme, _)| *scheme == signer.scheme())
+        .flat_map(|(_, algorithms)| algorithms.iter())
+        .any(|algorithm| {
+            end_entity
+                .verify_signature(*algorithm, MESSAGE, &signature)
+                .is_ok()
+        });
+}
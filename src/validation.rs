@@ -1,9 +1,12 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use crate::address_type::address_types;
 use crate::errors::ValidationError;
-use crate::query::{Envelope, LocationBias, Point};
-use crate::request::{PhotonReverseRequest, PhotonSearchRequest};
+use crate::query::{Envelope, LocationBias, Point, RankingProfile};
+use crate::request::{PhotonReverseRequest, PhotonSearchRequest, PhotonSimilarRequest};
+
+const SIMILAR_MAX_LIMIT: i64 = 50;
+const SIMILAR_MAX_RADIUS: u64 = 100;
 
 pub fn validate_search_request_parameters(
     request: &PhotonSearchRequest,
@@ -18,6 +21,41 @@ pub fn validate_search_request_parameters(
         validate_layers(layers)?
     }
 
+    let has_text_query = match &request.q {
+        Some(q) => !q.trim().is_empty(),
+        None => false,
+    };
+    if !has_text_query && request.osm_tag.is_none() && request.layer.is_none() {
+        return Err(ValidationError::MissingQuery);
+    }
+
+    if request.radius.is_some() && (request.lon.is_none() || request.lat.is_none()) {
+        return Err(ValidationError::Radius);
+    }
+
+    return Ok(());
+}
+
+pub fn validate_similar_request_parameters(
+    request: &PhotonSimilarRequest,
+) -> Result<(), ValidationError> {
+    if let Some(limit) = request.limit {
+        if !(1..=SIMILAR_MAX_LIMIT).contains(&limit) {
+            return Err(ValidationError::Limit {
+                value: limit,
+                max: SIMILAR_MAX_LIMIT,
+            });
+        }
+    }
+    if let Some(radius) = request.radius {
+        if !(1..=SIMILAR_MAX_RADIUS).contains(&radius) {
+            return Err(ValidationError::RadiusRange {
+                value: radius,
+                max: SIMILAR_MAX_RADIUS,
+            });
+        }
+    }
+
     return Ok(());
 }
 
@@ -36,13 +74,13 @@ pub fn validate_reverse_request_parameters(
 
 pub fn validate_lang_parameter(
     language: &Option<String>,
-    valid: &Vec<String>,
+    valid: &[String],
 ) -> Result<(), ValidationError> {
     if let Some(lang) = language {
         if !valid.contains(lang) {
             return Err(ValidationError::Lang {
                 value: lang.clone(),
-                valid: valid.clone(),
+                valid: valid.to_vec(),
             });
         }
     }
@@ -50,16 +88,53 @@ pub fn validate_lang_parameter(
     return Ok(());
 }
 
-fn validate_lon(lon: &f32) -> Result<(), ValidationError> {
-    if lon > &180.0 || lon < &-180.0 {
-        return Err(ValidationError::Lon(lon.clone()));
+pub fn validate_profile_parameter<'a>(
+    profile: &Option<String>,
+    profiles: &'a HashMap<String, RankingProfile>,
+) -> Result<&'a RankingProfile, ValidationError> {
+    let name = profile.as_deref().unwrap_or("default");
+
+    return match profiles.get(name) {
+        Some(profile) => Ok(profile),
+        None => {
+            let mut valid: Vec<String> = profiles.keys().cloned().collect();
+            valid.sort();
+            Err(ValidationError::Profile {
+                value: name.to_string(),
+                valid,
+            })
+        }
+    };
+}
+
+pub fn validate_lon(lon: &f32) -> Result<(), ValidationError> {
+    if !(-180.0..=180.0).contains(lon) {
+        return Err(ValidationError::Lon(*lon));
     }
     return Ok(());
 }
 
-fn validate_lat(lat: &f32) -> Result<(), ValidationError> {
-    if lat > &90.0 || lat < &-90.0 {
-        return Err(ValidationError::Lat(lat.clone()));
+pub fn validate_lat(lat: &f32) -> Result<(), ValidationError> {
+    if !(-90.0..=90.0).contains(lat) {
+        return Err(ValidationError::Lat(*lat));
+    }
+    return Ok(());
+}
+
+pub fn validate_geometry_tolerance(tolerance: &Option<f64>) -> Result<(), ValidationError> {
+    if let Some(tolerance) = tolerance {
+        if !tolerance.is_finite() || *tolerance < 0.0 {
+            return Err(ValidationError::GeometryTolerance(*tolerance));
+        }
+    }
+    return Ok(());
+}
+
+pub fn validate_max_count_drop(max_count_drop: &Option<f64>) -> Result<(), ValidationError> {
+    if let Some(max_count_drop) = max_count_drop {
+        if !(0.0..=1.0).contains(max_count_drop) {
+            return Err(ValidationError::MaxCountDrop(*max_count_drop));
+        }
     }
     return Ok(());
 }
@@ -77,7 +152,7 @@ pub fn validate_bbox(bbox: &Option<[f32; 4]>) -> Result<Option<Envelope>, Valida
             || bbox[0] > bbox[2]
             || bbox[1] > bbox[3]
         {
-            return Err(ValidationError::Bbox(bbox.clone()));
+            return Err(ValidationError::Bbox(*bbox));
         }
 
         return Ok(Some(Envelope {
@@ -93,7 +168,7 @@ pub fn validate_bbox(bbox: &Option<[f32; 4]>) -> Result<Option<Envelope>, Valida
 fn validate_layers(layers: &HashSet<String>) -> Result<(), ValidationError> {
     let layer_names: Vec<String> = address_types().iter().map(|a| a.name.into()).collect();
     for layer in layers {
-        if !layer_names.contains(&layer) {
+        if !layer_names.contains(layer) {
             return Err(ValidationError::Layer {
                 value: layer.clone(),
                 valid: layer_names.clone(),
@@ -0,0 +1,5 @@
+// Messages are generated from proto/vector_tile.proto, the Mapbox Vector Tile specification 2.1,
+// by build.rs
+tonic::include_proto!("vector_tile");
+
+pub use tile::{Feature, GeomType, Layer, Value};